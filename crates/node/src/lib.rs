@@ -1,10 +1,11 @@
-use utf16::IndexConverter;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tailwindcss_oxide::{PositionConverter, PositionEncoding};
 
 #[macro_use]
 extern crate napi_derive;
 
-mod utf16;
-
 #[derive(Debug, Clone)]
 #[napi(object)]
 pub struct ChangedContent {
@@ -14,7 +15,7 @@ pub struct ChangedContent {
   /// Contents of the changed file
   pub content: Option<String>,
 
-  /// File extension
+  /// File extension, all of them for compound extensions, e.g. `html.erb`
   pub extension: String,
 }
 
@@ -24,8 +25,47 @@ pub struct GlobEntry {
   /// Base path of the glob
   pub base: String,
 
-  /// Glob pattern
+  /// Glob pattern, patterns starting with `!` exclude files instead (e.g. `!src/legacy/**`)
+  pub pattern: String,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct InlineSource {
+  /// Candidates separated by whitespace, brace expansion is supported: `bg-{red,blue}-500`
+  pub candidates: String,
+
+  /// Drop the candidates from the output instead of adding them
+  pub negated: Option<bool>,
+}
+
+impl From<InlineSource> for tailwindcss_oxide::InlineSource {
+  fn from(source: InlineSource) -> Self {
+    Self {
+      candidates: source.candidates,
+      negated: source.negated.unwrap_or(false),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct PreProcessorMapping {
+  /// An extension (`jbuilder`, `html.slim`), or a glob (`app/views/**/*.html`)
   pub pattern: String,
+
+  /// Pre-processors that run on matching files, in order: `angular`, `razor`, `haml`, `heex`,
+  /// `pug`, `ruby`, `slim`, `svelte` or `vue`
+  pub pre_processors: Vec<String>,
+}
+
+impl From<PreProcessorMapping> for tailwindcss_oxide::PreProcessorMapping {
+  fn from(mapping: PreProcessorMapping) -> Self {
+    Self {
+      pattern: mapping.pattern,
+      pre_processors: mapping.pre_processors,
+    }
+  }
 }
 
 impl From<ChangedContent> for tailwindcss_oxide::ChangedContent<'_> {
@@ -73,12 +113,282 @@ impl From<tailwindcss_oxide::GlobEntry> for GlobEntry {
 pub struct ScannerOptions {
   /// Glob sources
   pub sources: Option<Vec<GlobEntry>>,
+
+  /// Candidates to add to (or remove from) the output directly
+  pub inline_sources: Option<Vec<InlineSource>>,
+
+  /// Detect changed files by hashing their contents instead of relying on modification times
+  pub content_hash: Option<bool>,
+
+  /// Upper bound in bytes for the file contents kept in memory at the same time while extracting
+  pub memory_limit: Option<i64>,
+
+  /// Amount of threads to scan with, `1` disables parallelism. Uses a shared pool if not set
+  pub threads: Option<u32>,
+
+  /// Only extract candidates that start with this prefix as their first variant, e.g. `tw`
+  pub prefix: Option<String>,
+
+  /// Separator between variants, e.g. `__` for `hover__flex`. Candidates are normalized to `:`
+  pub separator: Option<String>,
+
+  /// Only extract candidates inside of class contexts: `class` and `className` attributes, and
+  /// strings passed to `clsx`, `cva`, `cn` or `` tw`…` ``. CSS variables are still extracted
+  /// everywhere, but classes from Svelte `class:` directives or Haml and Slim `.card.p-4`
+  /// shorthands are not
+  pub strict: Option<bool>,
+
+  /// Additional attributes whose values contain classes in strict mode
+  pub class_attributes: Option<Vec<String>>,
+
+  /// Additional functions whose strings contain classes in strict mode
+  pub class_functions: Option<Vec<String>>,
+
+  /// Pre-processors for files that aren't pre-processed by default, or differently than by
+  /// default. Globs and more specific extensions (`html.erb` over `erb`) take precedence, then
+  /// later mappings
+  pub pre_processors: Option<Vec<PreProcessorMapping>>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ScanOptions {
+  /// Base path, relative sources are resolved against it
+  pub base: Option<String>,
+
+  /// Glob sources
+  pub sources: Option<Vec<GlobEntry>>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ScanResult {
+  /// All candidates, sorted
+  pub candidates: Vec<String>,
+
+  /// All scanned files
+  pub files: Vec<String>,
+
+  /// Optimized globs to watch
+  pub globs: Vec<GlobEntry>,
+
+  /// Problems found while scanning
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+#[napi]
+pub fn scan(opts: ScanOptions) -> ScanResult {
+  let result = tailwindcss_oxide::scan(tailwindcss_oxide::ScanOptions {
+    base: opts.base,
+    sources: opts
+      .sources
+      .map(|x| x.into_iter().map(Into::into).collect())
+      .unwrap_or_default(),
+  });
+
+  ScanResult {
+    candidates: result.candidates,
+    files: result.files,
+    globs: result.globs.into_iter().map(Into::into).collect(),
+    diagnostics: result.diagnostics.into_iter().map(Into::into).collect(),
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateSpan {
+  /// Start of the part inside of the candidate, in UTF-16 code units
+  pub start: i64,
+
+  /// End of the part (exclusive), so that `candidate.slice(start, end)` returns the part
+  pub end: i64,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateValue {
+  /// Kind of the value: `named`, `arbitrary` (`[…]`) or `variable` (`(…)`)
+  pub kind: String,
+
+  /// The value without the brackets or parens
+  pub span: CandidateSpan,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateVariant {
+  /// Kind of the variant: `named` or `arbitrary`
+  pub kind: String,
+
+  /// The whole variant, without the trailing `:`
+  pub span: CandidateSpan,
+
+  /// Root of a named variant, e.g. `group-hover` in `group-hover/card`
+  pub root: Option<CandidateSpan>,
+
+  /// Selector of an arbitrary variant, without the brackets
+  pub selector: Option<CandidateSpan>,
+
+  pub value: Option<CandidateValue>,
+
+  pub modifier: Option<CandidateValue>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateUtility {
+  /// Kind of the utility: `named` or `arbitrary` (an arbitrary property like `[color:red]`)
+  pub kind: String,
+
+  /// The whole utility, without the important flag
+  pub span: CandidateSpan,
+
+  /// The `-` of a negative utility
+  pub negative: Option<CandidateSpan>,
+
+  /// Root of a named utility. Only arbitrary values are split from the root, e.g. the root of
+  /// `bg-red-500` is `bg-red-500`, but the root of `bg-[#0088cc]` is `bg`
+  pub root: Option<CandidateSpan>,
+
+  /// Property of an arbitrary property
+  pub property: Option<CandidateSpan>,
+
+  pub value: Option<CandidateValue>,
+
+  pub modifier: Option<CandidateValue>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ParsedCandidate {
+  /// Variants in the order they appear in
+  pub variants: Vec<CandidateVariant>,
+
+  pub utility: CandidateUtility,
+
+  /// The `!` of an important candidate
+  pub important: Option<CandidateSpan>,
+}
+
+/// Converts the byte spans of a parsed candidate to UTF-16 spans
+struct SpanConverter<'a>(PositionConverter<'a>);
+
+impl SpanConverter<'_> {
+  fn span(&mut self, span: tailwindcss_oxide::Span) -> CandidateSpan {
+    CandidateSpan {
+      start: self.0.index(span.start) as i64,
+      end: self.0.index(span.end + 1) as i64,
+    }
+  }
+
+  fn value(&mut self, value: tailwindcss_oxide::Value) -> CandidateValue {
+    use tailwindcss_oxide::Value;
+
+    let (kind, span) = match value {
+      Value::Named(span) => ("named", span),
+      Value::Arbitrary(span) => ("arbitrary", span),
+      Value::Variable(span) => ("variable", span),
+    };
+
+    CandidateValue {
+      kind: kind.into(),
+      span: self.span(span),
+    }
+  }
+
+  fn variant(&mut self, variant: tailwindcss_oxide::Variant) -> CandidateVariant {
+    use tailwindcss_oxide::Variant;
+
+    match variant {
+      Variant::Arbitrary { span, selector } => CandidateVariant {
+        kind: "arbitrary".into(),
+        span: self.span(span),
+        root: None,
+        selector: Some(self.span(selector)),
+        value: None,
+        modifier: None,
+      },
+      Variant::Named {
+        span,
+        root,
+        value,
+        modifier,
+      } => CandidateVariant {
+        kind: "named".into(),
+        span: self.span(span),
+        root: Some(self.span(root)),
+        selector: None,
+        value: value.map(|x| self.value(x)),
+        modifier: modifier.map(|x| self.value(x)),
+      },
+    }
+  }
+
+  fn utility(&mut self, utility: tailwindcss_oxide::Utility) -> CandidateUtility {
+    use tailwindcss_oxide::Utility;
+
+    match utility {
+      Utility::Arbitrary {
+        span,
+        property,
+        value,
+        modifier,
+      } => CandidateUtility {
+        kind: "arbitrary".into(),
+        span: self.span(span),
+        negative: None,
+        root: None,
+        property: Some(self.span(property)),
+        value: Some(CandidateValue {
+          kind: "arbitrary".into(),
+          span: self.span(value),
+        }),
+        modifier: modifier.map(|x| self.value(x)),
+      },
+      Utility::Named {
+        span,
+        negative,
+        root,
+        value,
+        modifier,
+      } => CandidateUtility {
+        kind: "named".into(),
+        span: self.span(span),
+        negative: negative.map(|x| self.span(x)),
+        root: Some(self.span(root)),
+        property: None,
+        value: value.map(|x| self.value(x)),
+        modifier: modifier.map(|x| self.value(x)),
+      },
+    }
+  }
+}
+
+/// Parse a candidate into its variants and utility, `null` if it's not a valid candidate
+#[napi]
+pub fn parse_candidate(candidate: String) -> Option<ParsedCandidate> {
+  let parsed = tailwindcss_oxide::parse_candidate(candidate.as_bytes())?;
+  let mut converter = SpanConverter(PositionConverter::new(
+    candidate.as_bytes(),
+    PositionEncoding::Utf16,
+  ));
+
+  Some(ParsedCandidate {
+    variants: parsed
+      .variants
+      .into_iter()
+      .map(|x| converter.variant(x))
+      .collect(),
+    utility: converter.utility(parsed.utility),
+    important: parsed.important.map(|x| converter.span(x)),
+  })
 }
 
 #[derive(Debug, Clone)]
 #[napi]
 pub struct Scanner {
-  scanner: tailwindcss_oxide::Scanner,
+  /// Shared with the watcher thread, which updates the scanner for every batch of changes
+  scanner: Arc<Mutex<tailwindcss_oxide::Scanner>>,
 }
 
 #[derive(Debug, Clone)]
@@ -91,28 +401,337 @@ pub struct CandidateWithPosition {
   pub position: i64,
 }
 
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ScanChanges {
+  /// Candidates that are new since the previous call
+  pub added: Vec<String>,
+
+  /// Candidates that are not used anymore since the previous call
+  pub removed: Vec<String>,
+
+  /// Files that changed or were removed since the previous call
+  pub changed_files: Vec<String>,
+}
+
+impl From<tailwindcss_oxide::ScanChanges> for ScanChanges {
+  fn from(changes: tailwindcss_oxide::ScanChanges) -> Self {
+    Self {
+      added: changes.added,
+      removed: changes.removed,
+      changed_files: changes.changed_files,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateLocation {
+  /// Path of the file containing the candidate
+  pub file: String,
+
+  /// 1-based line number
+  pub line: i64,
+
+  /// 1-based column number, counted in UTF-16 code units like JavaScript string indexes
+  pub column: i64,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateWithLocations {
+  /// The candidate string
+  pub candidate: String,
+
+  /// All locations of the candidate across all scanned files
+  pub locations: Vec<CandidateLocation>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct DynamicClassWarning {
+  /// Where the class starts
+  pub location: CandidateLocation,
+
+  /// The class as it is written, e.g. `bg-${color}-500`
+  pub source: String,
+
+  /// `interpolation` or `concatenation`
+  pub kind: String,
+
+  /// Human readable description of the problem
+  pub message: String,
+}
+
+impl From<tailwindcss_oxide::DynamicClassWarning> for DynamicClassWarning {
+  fn from(warning: tailwindcss_oxide::DynamicClassWarning) -> Self {
+    let message = warning.to_string();
+
+    Self {
+      kind: match warning.kind {
+        tailwindcss_oxide::DynamicClassKind::Interpolation => "interpolation".into(),
+        tailwindcss_oxide::DynamicClassKind::Concatenation => "concatenation".into(),
+      },
+      location: warning.location.into(),
+      source: warning.source,
+      message,
+    }
+  }
+}
+
+impl From<tailwindcss_oxide::CandidateLocation> for CandidateLocation {
+  fn from(location: tailwindcss_oxide::CandidateLocation) -> Self {
+    Self {
+      file: location.file,
+      line: location.line as i64,
+      column: location.column as i64,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Diagnostic {
+  /// Kind of the problem: `unreadable-file`, `missing-source-base`, `invalid-brace-expansion`,
+  /// `empty-source`, `invalid-inline-source`, `invalid-separator` or `unknown-pre-processor`
+  pub kind: String,
+
+  /// Human readable description of the problem
+  pub message: String,
+
+  /// Path of the file that could not be read
+  pub path: Option<String>,
+
+  /// Base of the source that caused the problem
+  pub base: Option<String>,
+
+  /// Pattern of the source or pre-processor mapping that caused the problem
+  pub pattern: Option<String>,
+
+  /// Candidate of the inline source that caused the problem
+  pub candidate: Option<String>,
+
+  /// Separator that isn't valid
+  pub separator: Option<String>,
+
+  /// Name of the pre-processor that doesn't exist
+  pub name: Option<String>,
+}
+
+impl From<tailwindcss_oxide::Diagnostic> for Diagnostic {
+  fn from(diagnostic: tailwindcss_oxide::Diagnostic) -> Self {
+    use tailwindcss_oxide::Diagnostic::*;
+
+    let message = diagnostic.to_string();
+
+    match diagnostic {
+      UnreadableFile { path, .. } => Self {
+        kind: "unreadable-file".into(),
+        message,
+        path: Some(path.to_string_lossy().into()),
+        base: None,
+        pattern: None,
+        candidate: None,
+        separator: None,
+        name: None,
+      },
+      MissingSourceBase { base, pattern } => Self {
+        kind: "missing-source-base".into(),
+        message,
+        path: None,
+        base: Some(base),
+        pattern: Some(pattern),
+        candidate: None,
+        separator: None,
+        name: None,
+      },
+      InvalidBraceExpansion { base, pattern } => Self {
+        kind: "invalid-brace-expansion".into(),
+        message,
+        path: None,
+        base: Some(base),
+        pattern: Some(pattern),
+        candidate: None,
+        separator: None,
+        name: None,
+      },
+      EmptySource { base, pattern } => Self {
+        kind: "empty-source".into(),
+        message,
+        path: None,
+        base: Some(base),
+        pattern: Some(pattern),
+        candidate: None,
+        separator: None,
+        name: None,
+      },
+      InvalidInlineSource { candidate } => Self {
+        kind: "invalid-inline-source".into(),
+        message,
+        path: None,
+        base: None,
+        pattern: None,
+        candidate: Some(candidate),
+        separator: None,
+        name: None,
+      },
+      InvalidSeparator { separator } => Self {
+        kind: "invalid-separator".into(),
+        message,
+        path: None,
+        base: None,
+        pattern: None,
+        candidate: None,
+        separator: Some(separator),
+        name: None,
+      },
+      UnknownPreProcessor { pattern, name } => Self {
+        kind: "unknown-pre-processor".into(),
+        message,
+        path: None,
+        base: None,
+        pattern: Some(pattern),
+        candidate: None,
+        separator: None,
+        name: Some(name),
+      },
+    }
+  }
+}
+
+impl Scanner {
+  fn inner(&self) -> MutexGuard<'_, tailwindcss_oxide::Scanner> {
+    self.scanner.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
 #[napi]
 impl Scanner {
   #[napi(constructor)]
   pub fn new(opts: ScannerOptions) -> Self {
+    let change_detection = match opts.content_hash {
+      Some(true) => tailwindcss_oxide::ChangeDetection::ContentHash,
+      _ => tailwindcss_oxide::ChangeDetection::Mtime,
+    };
+
+    let mut scanner = tailwindcss_oxide::Scanner::new(
+      opts
+        .sources
+        .map(|x| x.into_iter().map(Into::into).collect()),
+    )
+    .with_change_detection(change_detection)
+    .with_position_encoding(PositionEncoding::Utf16)
+    .with_inline_sources(
+      opts
+        .inline_sources
+        .map(|x| x.into_iter().map(Into::into).collect())
+        .unwrap_or_default(),
+    );
+
+    if let Some(memory_limit) = opts.memory_limit.and_then(|x| usize::try_from(x).ok()) {
+      scanner = scanner.with_memory_limit(memory_limit);
+    }
+
+    if let Some(threads) = opts.threads {
+      scanner = scanner.with_threads(threads as usize);
+    }
+
+    if let Some(prefix) = opts.prefix {
+      scanner = scanner.with_prefix(prefix);
+    }
+
+    if let Some(separator) = opts.separator {
+      scanner = scanner.with_separator(separator);
+    }
+
+    if opts.strict == Some(true) {
+      let mut class_context = tailwindcss_oxide::ClassContext::default();
+      class_context
+        .attributes
+        .extend(opts.class_attributes.unwrap_or_default());
+      class_context
+        .functions
+        .extend(opts.class_functions.unwrap_or_default());
+
+      scanner = scanner.with_class_context(class_context);
+    }
+
+    if let Some(pre_processors) = opts.pre_processors {
+      scanner = scanner.with_pre_processors(pre_processors.into_iter().map(Into::into).collect());
+    }
+
     Self {
-      scanner: tailwindcss_oxide::Scanner::new(
-        opts
-          .sources
-          .map(|x| x.into_iter().map(Into::into).collect()),
-      ),
+      scanner: Arc::new(Mutex::new(scanner)),
     }
   }
 
   #[napi]
   pub fn scan(&mut self) -> Vec<String> {
-    self.scanner.scan()
+    self.inner().scan()
+  }
+
+  /// Scan for changes since the previous call. Don't mix this with `watch`, both share the same
+  /// changes, so each change is reported to only one of them.
+  #[napi]
+  pub fn scan_changes(&mut self) -> ScanChanges {
+    self.inner().scan_changes().into()
+  }
+
+  /// Watch all sources for changes. The callback is called with the changes of every debounced
+  /// batch of file system events, until `unwatch` is called.
+  ///
+  /// The callback receives the changes since the previous batch or the previous call to
+  /// `scanChanges`, so calling `scanChanges` while watching hides those changes from the callback.
+  #[napi]
+  pub fn watch(
+    &mut self,
+    callback: ThreadsafeFunction<ScanChanges, ErrorStrategy::Fatal>,
+    debounce: Option<u32>,
+  ) -> napi::Result<()> {
+    let debounce = Duration::from_millis(debounce.unwrap_or(50).into());
+
+    let batches = self
+      .inner()
+      .watch(debounce)
+      .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+
+    // Don't keep the scanner alive just because we are watching
+    let scanner = Arc::downgrade(&self.scanner);
+
+    std::thread::spawn(move || {
+      for batch in batches {
+        let Some(scanner) = scanner.upgrade() else {
+          return;
+        };
+
+        let changes = scanner
+          .lock()
+          .unwrap_or_else(PoisonError::into_inner)
+          .scan_watch_batch(&batch);
+
+        if changes.added.is_empty()
+          && changes.removed.is_empty()
+          && changes.changed_files.is_empty()
+        {
+          continue;
+        }
+
+        callback.call(changes.into(), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    });
+
+    Ok(())
+  }
+
+  #[napi]
+  pub fn unwatch(&mut self) {
+    self.inner().unwatch()
   }
 
   #[napi]
   pub fn scan_files(&mut self, input: Vec<ChangedContent>) -> Vec<String> {
     self
-      .scanner
+      .inner()
       .scan_content(input.into_iter().map(Into::into).collect())
   }
 
@@ -131,28 +750,80 @@ impl Scanner {
       extension: input.extension,
     };
 
-    let mut utf16_idx = IndexConverter::new(&content[..]);
+    let mut converter = PositionConverter::new(content.as_bytes(), PositionEncoding::Utf16);
 
     self
-      .scanner
+      .inner()
       .get_candidates_with_positions(input.into())
       .into_iter()
       .map(|(candidate, position)| CandidateWithPosition {
         candidate,
-        position: utf16_idx.get(position),
+        position: converter.index(position) as i64,
       })
       .collect()
   }
 
+  #[napi]
+  pub fn get_candidate_locations(&mut self) -> Vec<CandidateWithLocations> {
+    self
+      .inner()
+      .get_candidate_locations()
+      .into_iter()
+      .map(|(candidate, locations)| CandidateWithLocations {
+        candidate,
+        locations: locations.into_iter().map(Into::into).collect(),
+      })
+      .collect()
+  }
+
+  /// Classes that are constructed at runtime in the scanned files, e.g. `bg-${color}-500`
+  #[napi]
+  pub fn get_dynamic_class_warnings(&mut self) -> Vec<DynamicClassWarning> {
+    self
+      .inner()
+      .get_dynamic_class_warnings()
+      .into_iter()
+      .map(Into::into)
+      .collect()
+  }
+
+  /// Restore the scanner from a cache file. Returns `false` if the cache doesn't exist or can't be
+  /// used, in which case the scanner starts from scratch.
+  #[napi]
+  pub fn load_cache(&mut self, path: String) -> bool {
+    self
+      .inner()
+      .load_cache(std::path::Path::new(&path))
+      .unwrap_or(false)
+  }
+
+  #[napi]
+  pub fn save_cache(&mut self, path: String) -> napi::Result<()> {
+    self
+      .inner()
+      .save_cache(std::path::Path::new(&path))
+      .map_err(|err| napi::Error::from_reason(err.to_string()))
+  }
+
   #[napi(getter)]
   pub fn files(&mut self) -> Vec<String> {
-    self.scanner.get_files()
+    self.inner().get_files()
+  }
+
+  #[napi(getter)]
+  pub fn diagnostics(&mut self) -> Vec<Diagnostic> {
+    self
+      .inner()
+      .get_diagnostics()
+      .into_iter()
+      .map(Into::into)
+      .collect()
   }
 
   #[napi(getter)]
   pub fn globs(&mut self) -> Vec<GlobEntry> {
     self
-      .scanner
+      .inner()
       .get_globs()
       .into_iter()
       .map(Into::into)
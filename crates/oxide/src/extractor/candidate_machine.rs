@@ -1,9 +1,11 @@
 use crate::cursor;
 use crate::extractor::boundary::{has_valid_boundaries, is_valid_before_boundary};
 use crate::extractor::machine::{Machine, MachineState};
+use crate::extractor::separator::Separator;
 use crate::extractor::utility_machine::UtilityMachine;
 use crate::extractor::variant_machine::VariantMachine;
 use crate::extractor::Span;
+use crate::fast_skip::fast_skip;
 
 /// Extract full candidates including variants and utilities.
 #[derive(Debug, Default)]
@@ -14,6 +16,12 @@ pub struct CandidateMachine {
     /// End position of the last variant (if any)
     last_variant_end_pos: Option<usize>,
 
+    /// Only accept candidates that use this prefix as their first variant (without the `:`)
+    prefix: Option<Box<[u8]>>,
+
+    /// Separates the variants from each other and from the utility
+    separator: Separator,
+
     utility_machine: UtilityMachine,
     variant_machine: VariantMachine,
 }
@@ -31,10 +39,13 @@ impl Machine for CandidateMachine {
 
         while cursor.pos < len {
             // Skip ahead for known characters that will never be part of a candidate. No need to
-            // run any sub-machines.
+            // run any sub-machines. Runs of whitespace are skipped in one go.
             if cursor.curr.is_ascii_whitespace() {
                 self.reset();
-                cursor.advance();
+                match fast_skip(cursor) {
+                    Some(pos) => cursor.move_to(pos),
+                    None => cursor.advance(),
+                }
                 continue;
             }
 
@@ -70,6 +81,28 @@ impl Machine for CandidateMachine {
                 continue;
             }
 
+            // When a prefix is configured, a candidate has to start with it. Anything else can be
+            // skipped until the next boundary without running any sub-machines.
+            //
+            // E.g.: `tw:flex items-center`
+            //        ^^^                   Prefix, continue
+            //                ^^^^^^^^^^^^  No prefix, skip
+            //
+            if self.last_variant_end_pos.is_none() && !self.at_prefix(cursor) {
+                if let Some(offset) = cursor.input[cursor.pos..]
+                    .iter()
+                    .position(|&c| is_valid_before_boundary(&c))
+                {
+                    self.reset();
+                    cursor.advance_by(offset + 1);
+                } else {
+                    cursor.move_to(len);
+                    return self.restart();
+                }
+
+                continue;
+            }
+
             let mut variant_cursor = cursor.clone();
             let variant_machine_state = self.variant_machine.next(&mut variant_cursor);
 
@@ -90,6 +123,8 @@ impl Machine for CandidateMachine {
 
                             Span::new(self.start_pos, utility_span.end)
                         }
+                        // Without a variant, the prefix is missing
+                        None if self.prefix.is_some() => return self.restart(),
                         None => utility_span,
                     };
 
@@ -124,9 +159,14 @@ impl Machine for CandidateMachine {
                         // E.g.: `{ underline: true }`
                         //          ^^^^^^^^^^        Variant
                         //          ^^^^^^^^^         Utility (followed by `: `)
-                        let after = cursor.input.get(utility_span.end + 2).unwrap_or(&b'\0');
-                        if after.is_ascii_whitespace() || *after == b'\0' {
-                            cursor.move_to(utility_cursor.pos + 2);
+                        //
+                        // With a prefix, the variant is always the prefix itself.
+                        let after = cursor.input.get(variant_span.end + 1).unwrap_or(&b'\0');
+                        if self.prefix.is_none()
+                            && utility_span.end + self.separator.len() == variant_span.end
+                            && (after.is_ascii_whitespace() || *after == b'\0')
+                        {
+                            cursor.move_to(variant_span.end + 1);
                             return self.done_span(utility_span);
                         }
 
@@ -170,6 +210,49 @@ impl Machine for CandidateMachine {
 }
 
 impl CandidateMachine {
+    /// Only extract candidates that use `prefix` as their first variant, e.g. `tw` for
+    /// `tw:flex` and `tw:hover:underline`.
+    pub fn with_prefix(mut self, prefix: &[u8]) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Use a custom separator between variants, e.g. `__` for `hover__underline`.
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self.variant_machine = VariantMachine::with_separator(separator);
+        self
+    }
+
+    /// A new machine with the same prefix and separator
+    pub fn fresh(&self) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            ..Default::default()
+        }
+        .with_separator(self.separator)
+    }
+
+    pub fn prefix(&self) -> Option<&[u8]> {
+        self.prefix.as_deref()
+    }
+
+    pub fn separator(&self) -> Separator {
+        self.separator
+    }
+
+    /// Whether the cursor is at the start of the prefix variant, always true without a prefix.
+    #[inline(always)]
+    fn at_prefix(&self, cursor: &cursor::Cursor<'_>) -> bool {
+        match &self.prefix {
+            None => true,
+            Some(prefix) => {
+                let rest = &cursor.input[cursor.pos..];
+                rest.starts_with(prefix) && self.separator.is_at(rest, prefix.len())
+            }
+        }
+    }
+
     #[inline(always)]
     fn done_span(&mut self, span: Span) -> MachineState {
         self.reset();
@@ -179,7 +262,7 @@ impl CandidateMachine {
 
 #[cfg(test)]
 mod tests {
-    use super::CandidateMachine;
+    use super::{CandidateMachine, Separator};
     use crate::extractor::machine::Machine;
 
     #[test]
@@ -327,4 +410,88 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_candidate_extraction_with_prefix() {
+        for (input, expected) in [
+            // Prefix as the only variant
+            ("tw:flex", vec!["tw:flex"]),
+            // Prefix followed by other variants
+            ("tw:hover:focus:flex", vec!["tw:hover:focus:flex"]),
+            ("tw:[&>*]:flex", vec!["tw:[&>*]:flex"]),
+            // Prefix with an arbitrary property
+            ("tw:[color:red]", vec!["tw:[color:red]"]),
+            // Missing prefix
+            ("flex", vec![]),
+            ("hover:flex", vec![]),
+            ("[color:red]", vec![]),
+            // Prefix is not the first variant
+            ("hover:tw:flex", vec![]),
+            // Prefix as part of another variant or utility
+            ("tw-flex", vec![]),
+            ("twx:flex", vec![]),
+            ("xtw:flex", vec![]),
+            // JavaScript keys are not prefixed
+            ("{ tw: true }", vec![]),
+            // Multiple candidates
+            (
+                r#"<div class="tw:flex items-center tw:sm:p-4">"#,
+                vec!["tw:flex", "tw:sm:p-4"],
+            ),
+        ] {
+            let machine = CandidateMachine::default().with_prefix(b"tw");
+            let actual = CandidateMachine::test_extract_all_with(machine, input);
+
+            assert_eq!(actual, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_candidate_extraction_with_separator() {
+        for (separator, input, expected) in [
+            // Single variant
+            ("_", "hover_flex", vec!["hover_flex"]),
+            ("__", "hover__flex", vec!["hover__flex"]),
+            // Multiple variants
+            ("__", "md__hover__flex", vec!["md__hover__flex"]),
+            // Single character and arbitrary variants
+            ("__", "*__flex", vec!["*__flex"]),
+            ("__", "[&>*]__p-4", vec!["[&>*]__p-4"]),
+            ("__", "data-[open]__flex", vec!["data-[open]__flex"]),
+            (
+                "__",
+                "group-hover/card__flex",
+                vec!["group-hover/card__flex"],
+            ),
+            // Underscores inside of arbitrary values are not separators
+            (
+                "_",
+                "md_grid-cols-[1fr_auto]",
+                vec!["md_grid-cols-[1fr_auto]"],
+            ),
+            // A single underscore is not a `__` separator, but a utility
+            ("__", "hover_flex", vec!["hover_flex"]),
+            // The `:` is not a separator anymore
+            ("__", "hover:flex", vec![]),
+            // Utilities without variants
+            ("__", "flex items-center", vec!["flex", "items-center"]),
+            // Prefix
+            ("__", "tw__hover__flex", vec!["tw__hover__flex"]),
+        ] {
+            let separator = Separator::new(separator).unwrap();
+            let machine = CandidateMachine::default().with_separator(separator);
+            let actual = CandidateMachine::test_extract_all_with(machine, input);
+
+            assert_eq!(actual, expected, "separator: {separator}, input: {input}");
+        }
+
+        // Prefix and separator combined
+        let machine = CandidateMachine::default()
+            .with_prefix(b"tw")
+            .with_separator(Separator::new("__").unwrap());
+        let actual =
+            CandidateMachine::test_extract_all_with(machine, "tw__hover__flex hover__flex");
+
+        assert_eq!(actual, vec!["tw__hover__flex"]);
+    }
 }
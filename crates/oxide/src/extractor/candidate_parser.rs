@@ -0,0 +1,559 @@
+//! Parse a single candidate into its parts, e.g.:
+//!
+//! ```text
+//! group-hover/card:-translate-x-[10px]/50!
+//! ^^^^^^^^^^^                                Variant root
+//!             ^^^^                           Variant modifier
+//!                  ^                         Negative
+//!                   ^^^^^^^^^^^              Utility root
+//!                               ^^^^         Utility value
+//!                                     ^^     Utility modifier
+//!                                       ^    Important
+//! ```
+//!
+//! The same machines that extract candidates decide where variants and the utility start and
+//! end, so everything the extractor emits can be parsed. This is purely syntactic: which part of
+//! a named utility like `bg-red-500` is the root and which part is the value depends on the
+//! utilities that exist, so the named part is reported as the root as a whole.
+use crate::cursor;
+use crate::extractor::machine::{Machine, MachineState, Span};
+use crate::extractor::separator::Separator;
+use crate::extractor::utility_machine::UtilityMachine;
+use crate::extractor::variant_machine::VariantMachine;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The whole candidate
+    pub span: Span,
+
+    /// Variants in the order they appear in
+    pub variants: Vec<Variant>,
+
+    pub utility: Utility,
+
+    /// The `!` of an important candidate, e.g. `flex!`, or the legacy `!flex`
+    pub important: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Variant {
+    /// E.g.: `[&_p]:flex`
+    Arbitrary {
+        /// The variant without the trailing `:`
+        span: Span,
+
+        /// The selector without the brackets, e.g. `&_p`
+        selector: Span,
+    },
+
+    /// E.g.: `hover:flex`, `group-hover/card:flex`, `data-[state=open]:flex`
+    Named {
+        /// The variant without the trailing `:`
+        span: Span,
+
+        /// E.g. `group-hover` in `group-hover/card`, or `data` in `data-[state=open]`
+        root: Span,
+
+        value: Option<Value>,
+
+        modifier: Option<Value>,
+    },
+}
+
+impl Variant {
+    /// The variant without the trailing `:`
+    pub fn span(&self) -> Span {
+        match self {
+            Variant::Arbitrary { span, .. } | Variant::Named { span, .. } => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Utility {
+    /// E.g.: `[color:red]/50`
+    Arbitrary {
+        /// The utility without the important flag
+        span: Span,
+
+        /// E.g. `color` in `[color:red]`
+        property: Span,
+
+        /// E.g. `red` in `[color:red]`
+        value: Span,
+
+        modifier: Option<Value>,
+    },
+
+    /// E.g.: `flex`, `-mt-4`, `bg-red-500/50`, `bg-[#0088cc]`, `bg-(--my-color)`
+    Named {
+        /// The utility without the important flag
+        span: Span,
+
+        /// The `-` of a negative utility, e.g. `-mt-4`
+        negative: Option<Span>,
+
+        /// E.g. `bg-red-500` in `bg-red-500/50`, or `bg` in `bg-[#0088cc]`
+        root: Span,
+
+        /// Only arbitrary values and variables are split from the root
+        value: Option<Value>,
+
+        modifier: Option<Value>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    /// E.g. `50` in `bg-red-500/50`
+    Named(Span),
+
+    /// Without the brackets, e.g. `#0088cc` in `bg-[#0088cc]`
+    Arbitrary(Span),
+
+    /// Without the parens, e.g. `--my-color` in `bg-(--my-color)`
+    Variable(Span),
+}
+
+/// Parse a candidate into its variants and utility. Returns `None` if the input as a whole is not
+/// a valid candidate.
+pub fn parse_candidate(input: &[u8]) -> Option<Candidate> {
+    parse_candidate_with_separator(input, Separator::default())
+}
+
+/// Parse a candidate that uses a custom separator between its variants, e.g. `hover__flex`.
+pub fn parse_candidate_with_separator(input: &[u8], separator: Separator) -> Option<Candidate> {
+    let len = input.len();
+    if len == 0 {
+        return None;
+    }
+
+    let mut cursor = cursor::Cursor::new(input);
+
+    let mut variants = vec![];
+    let mut variant_machine = VariantMachine::with_separator(separator);
+
+    loop {
+        let mut variant_cursor = cursor.clone();
+        match variant_machine.next(&mut variant_cursor) {
+            MachineState::Done(span) if span.start == cursor.pos => {
+                variants.push(parse_variant(input, span, separator));
+                cursor.move_to(span.end + 1);
+            }
+            _ => break,
+        }
+    }
+
+    // The utility has to cover the rest of the input
+    let start = cursor.pos;
+    match UtilityMachine::default().next(&mut cursor) {
+        MachineState::Done(span) if span.start == start && span.end == len - 1 => {}
+        _ => return None,
+    }
+
+    let (utility, important) = parse_utility(input, Span::new(start, len - 1));
+
+    Some(Candidate {
+        span: Span::new(0, len - 1),
+        variants,
+        utility,
+        important,
+    })
+}
+
+/// Replace a custom separator in a candidate with the canonical `:`, e.g. `hover__flex` becomes
+/// `hover:flex`. Separators inside of arbitrary values are kept as-is. Candidates that can't be
+/// parsed are returned unchanged.
+pub fn normalize_separator(input: &[u8], separator: Separator) -> Cow<'_, [u8]> {
+    if separator.is_default() {
+        return Cow::Borrowed(input);
+    }
+
+    let Some(candidate) = parse_candidate_with_separator(input, separator) else {
+        return Cow::Borrowed(input);
+    };
+
+    let Some(last) = candidate.variants.last() else {
+        return Cow::Borrowed(input);
+    };
+
+    let mut normalized = Vec::with_capacity(input.len());
+    for variant in &candidate.variants {
+        normalized.extend_from_slice(variant.span().slice(input));
+        normalized.push(b':');
+    }
+    normalized.extend_from_slice(&input[last.span().end + 1 + separator.len()..]);
+
+    Cow::Owned(normalized)
+}
+
+/// The span includes the trailing separator
+fn parse_variant(input: &[u8], span: Span, separator: Separator) -> Variant {
+    let span = Span::new(span.start, span.end - separator.len());
+
+    if input[span.start] == b'[' {
+        return Variant::Arbitrary {
+            span,
+            selector: Span::new(span.start + 1, span.end - 1),
+        };
+    }
+
+    let (named, modifier) = split_modifier(input, span);
+    let (root, value) = split_value(input, named);
+
+    Variant::Named {
+        span,
+        root,
+        value,
+        modifier,
+    }
+}
+
+fn parse_utility(input: &[u8], span: Span) -> (Utility, Option<Span>) {
+    let (span, important) = if input[span.start] == b'!' {
+        (
+            Span::new(span.start + 1, span.end),
+            Some(Span::new(span.start, span.start)),
+        )
+    } else if input[span.end] == b'!' {
+        (
+            Span::new(span.start, span.end - 1),
+            Some(Span::new(span.end, span.end)),
+        )
+    } else {
+        (span, None)
+    };
+
+    let (base, modifier) = split_modifier(input, span);
+
+    // E.g.: `[color:red]`
+    if input[base.start] == b'[' {
+        let colon = base.start
+            + input[base.start..=base.end]
+                .iter()
+                .position(|x| *x == b':')
+                .unwrap_or_default();
+
+        let utility = Utility::Arbitrary {
+            span,
+            property: Span::new(base.start + 1, colon - 1),
+            value: Span::new(colon + 1, base.end - 1),
+            modifier,
+        };
+
+        return (utility, important);
+    }
+
+    let (negative, base) = if input[base.start] == b'-' {
+        (
+            Some(Span::new(base.start, base.start)),
+            Span::new(base.start + 1, base.end),
+        )
+    } else {
+        (None, base)
+    };
+
+    let (root, value) = split_value(input, base);
+
+    let utility = Utility::Named {
+        span,
+        negative,
+        root,
+        value,
+        modifier,
+    };
+
+    (utility, important)
+}
+
+/// Split `bg-red-500/50` into `bg-red-500` and the modifier `50`. Slashes inside of arbitrary
+/// values are not considered.
+fn split_modifier(input: &[u8], span: Span) -> (Span, Option<Value>) {
+    let mut depth = 0usize;
+
+    for idx in span.start..=span.end {
+        match input[idx] {
+            b'[' | b'(' => depth += 1,
+            b']' | b')' => depth = depth.saturating_sub(1),
+            b'/' if depth == 0 && idx > span.start => {
+                return (
+                    Span::new(span.start, idx - 1),
+                    Some(parse_value(input, Span::new(idx + 1, span.end))),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    (span, None)
+}
+
+/// Split `bg-[#0088cc]` into the root `bg` and the value `#0088cc`. The machines only allow
+/// arbitrary values at the end, so the first bracket or paren starts the value.
+fn split_value(input: &[u8], span: Span) -> (Span, Option<Value>) {
+    let Some(offset) = input[span.start..=span.end]
+        .iter()
+        .position(|x| matches!(x, b'[' | b'('))
+    else {
+        return (span, None);
+    };
+
+    let open = span.start + offset;
+
+    // E.g.: `@[400px]` doesn't have a `-` in between
+    let root_end = match input[open - 1] {
+        b'-' => open - 2,
+        _ => open - 1,
+    };
+
+    (
+        Span::new(span.start, root_end),
+        Some(parse_value(input, Span::new(open, span.end))),
+    )
+}
+
+fn parse_value(input: &[u8], span: Span) -> Value {
+    let inner = || Span::new(span.start + 1, span.end - 1);
+
+    match input[span.start] {
+        b'[' => Value::Arbitrary(inner()),
+        b'(' => Value::Variable(inner()),
+        _ => Value::Named(span),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_separator, parse_candidate, Utility, Value, Variant};
+    use crate::extractor::machine::Span;
+    use crate::extractor::separator::Separator;
+
+    /// Describe every part of a candidate, e.g. `variant(hover)` or `root(flex)`
+    fn describe(input: &str) -> Option<Vec<String>> {
+        let candidate = parse_candidate(input.as_bytes())?;
+        let text = |span: Span| &input[span.start..=span.end];
+        let value = |name: &str, value: Value| match value {
+            Value::Named(span) => format!("{name}({})", text(span)),
+            Value::Arbitrary(span) => format!("{name}([{}])", text(span)),
+            Value::Variable(span) => format!("{name}(({}))", text(span)),
+        };
+
+        let mut parts = vec![];
+
+        for variant in candidate.variants {
+            match variant {
+                Variant::Arbitrary { selector, .. } => {
+                    parts.push(format!("selector({})", text(selector)))
+                }
+                Variant::Named {
+                    root,
+                    value: variant_value,
+                    modifier,
+                    ..
+                } => {
+                    parts.push(format!("variant({})", text(root)));
+                    parts.extend(variant_value.map(|x| value("variant-value", x)));
+                    parts.extend(modifier.map(|x| value("variant-modifier", x)));
+                }
+            }
+        }
+
+        match candidate.utility {
+            Utility::Arbitrary {
+                property,
+                value: property_value,
+                modifier,
+                ..
+            } => {
+                parts.push(format!("property({})", text(property)));
+                parts.push(format!("value({})", text(property_value)));
+                parts.extend(modifier.map(|x| value("modifier", x)));
+            }
+            Utility::Named {
+                negative,
+                root,
+                value: utility_value,
+                modifier,
+                ..
+            } => {
+                parts.extend(negative.map(|x| format!("negative({})", text(x))));
+                parts.push(format!("root({})", text(root)));
+                parts.extend(utility_value.map(|x| value("value", x)));
+                parts.extend(modifier.map(|x| value("modifier", x)));
+            }
+        }
+
+        parts.extend(
+            candidate
+                .important
+                .map(|x| format!("important({})", x.start)),
+        );
+
+        Some(parts)
+    }
+
+    #[test]
+    fn test_parse_candidate() {
+        for (input, expected) in [
+            // Simple utilities
+            ("flex", vec!["root(flex)"]),
+            ("@container", vec!["root(@container)"]),
+            ("bg-red-500", vec!["root(bg-red-500)"]),
+            ("px-2.5", vec!["root(px-2.5)"]),
+            // Negative utilities
+            ("-mt-4", vec!["negative(-)", "root(mt-4)"]),
+            // Important
+            ("flex!", vec!["root(flex)", "important(4)"]),
+            ("!flex", vec!["root(flex)", "important(0)"]),
+            // Modifiers
+            ("bg-red-500/50", vec!["root(bg-red-500)", "modifier(50)"]),
+            (
+                "bg-red-500/[50%]",
+                vec!["root(bg-red-500)", "modifier([50%])"],
+            ),
+            (
+                "bg-red-500/(--my-opacity)",
+                vec!["root(bg-red-500)", "modifier((--my-opacity))"],
+            ),
+            // Arbitrary values
+            ("bg-[#0088cc]", vec!["root(bg)", "value([#0088cc])"]),
+            (
+                "bg-[url(/img/a.png)]/50",
+                vec!["root(bg)", "value([url(/img/a.png)])", "modifier(50)"],
+            ),
+            (
+                "-translate-x-[10px]!",
+                vec![
+                    "negative(-)",
+                    "root(translate-x)",
+                    "value([10px])",
+                    "important(19)",
+                ],
+            ),
+            // Arbitrary variables
+            ("bg-(--my-color)", vec!["root(bg)", "value((--my-color))"]),
+            // Arbitrary properties
+            ("[color:red]", vec!["property(color)", "value(red)"]),
+            (
+                "[--my-var:calc(1px+2px)]/50!",
+                vec![
+                    "property(--my-var)",
+                    "value(calc(1px+2px))",
+                    "modifier(50)",
+                    "important(27)",
+                ],
+            ),
+            // Variants
+            ("hover:flex", vec!["variant(hover)", "root(flex)"]),
+            (
+                "hover:focus:flex",
+                vec!["variant(hover)", "variant(focus)", "root(flex)"],
+            ),
+            ("*:flex", vec!["variant(*)", "root(flex)"]),
+            (
+                "group-hover/card:flex",
+                vec![
+                    "variant(group-hover)",
+                    "variant-modifier(card)",
+                    "root(flex)",
+                ],
+            ),
+            (
+                "data-[state=open]:flex",
+                vec!["variant(data)", "variant-value([state=open])", "root(flex)"],
+            ),
+            (
+                "supports-(--my-var):flex",
+                vec![
+                    "variant(supports)",
+                    "variant-value((--my-var))",
+                    "root(flex)",
+                ],
+            ),
+            (
+                "@[400px]:flex",
+                vec!["variant(@)", "variant-value([400px])", "root(flex)"],
+            ),
+            // Arbitrary variants
+            (
+                "[&>[data-slot=icon]:last-child]:sm:right-2.5",
+                vec![
+                    "selector(&>[data-slot=icon]:last-child)",
+                    "variant(sm)",
+                    "root(right-2.5)",
+                ],
+            ),
+        ] {
+            let expected = expected.into_iter().map(String::from).collect();
+            assert_eq!(describe(input), Some(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_candidate() {
+        for input in [
+            "",
+            "hover:",
+            "flex block",
+            "flex!block",
+            "!flex!",
+            "bg-[#0088cc",
+            "bg-red-500/50/50",
+            "'flex'",
+            "flex$",
+        ] {
+            assert_eq!(describe(input), None, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_candidate_spans() {
+        let candidate = parse_candidate(b"hover:bg-[red]").unwrap();
+
+        assert_eq!(candidate.span, Span::new(0, 13));
+        assert_eq!(
+            candidate.variants,
+            vec![Variant::Named {
+                span: Span::new(0, 4),
+                root: Span::new(0, 4),
+                value: None,
+                modifier: None,
+            }]
+        );
+        assert_eq!(
+            candidate.utility,
+            Utility::Named {
+                span: Span::new(6, 13),
+                negative: None,
+                root: Span::new(6, 7),
+                value: Some(Value::Arbitrary(Span::new(10, 12))),
+                modifier: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_normalize_separator() {
+        for (separator, input, expected) in [
+            ("__", "hover__flex", "hover:flex"),
+            ("__", "md__hover__bg-red-500/50", "md:hover:bg-red-500/50"),
+            ("__", "[&_p]__underline", "[&_p]:underline"),
+            ("__", "group-hover/card__flex!", "group-hover/card:flex!"),
+            ("_", "md_grid-cols-[1fr_auto]", "md:grid-cols-[1fr_auto]"),
+            // Nothing to normalize
+            ("__", "flex", "flex"),
+            ("__", "grid-cols-[1fr__auto]", "grid-cols-[1fr__auto]"),
+            (":", "hover:flex", "hover:flex"),
+            // Not a valid candidate
+            ("__", "hover__", "hover__"),
+        ] {
+            let separator = Separator::new(separator).unwrap();
+            assert_eq!(
+                normalize_separator(input.as_bytes(), separator),
+                expected.as_bytes(),
+                "separator: {separator}, input: {input}"
+            );
+        }
+    }
+}
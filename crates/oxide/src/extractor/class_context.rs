@@ -0,0 +1,291 @@
+//! Find the parts of the input that contain classes, e.g.:
+//!
+//! ```text
+//! <div class="flex items-center" title="Some text">
+//!             ^^^^^^^^^^^^^^^^^^
+//! <Button className={clsx('px-4', isActive && 'bg-blue-500')} />
+//!                          ^^^^               ^^^^^^^^^^^
+//! const title = tw`text-xl font-bold`
+//!                  ^^^^^^^^^^^^^^^^^
+//! ```
+//!
+//! Contexts are the values of class attributes (`class="…"`, `className={…}`, or
+//! `className: "…"` in compiled JavaScript), and the strings passed to class helpers (`clsx(…)` or
+//! `` tw`…` ``). Inside of `{…}` and `(…)` only string literals are used, so variables and
+//! conditions are never extracted.
+use crate::cursor;
+use crate::extractor::machine::{Machine, MachineState};
+use crate::extractor::string_machine::StringMachine;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassContext {
+    /// Attributes and object keys whose values contain classes
+    pub attributes: Vec<String>,
+
+    /// Functions and tagged templates whose strings contain classes
+    pub functions: Vec<String>,
+}
+
+impl Default for ClassContext {
+    fn default() -> Self {
+        Self {
+            attributes: vec!["class".into(), "className".into()],
+            functions: vec!["clsx".into(), "cva".into(), "cn".into(), "tw".into()],
+        }
+    }
+}
+
+impl ClassContext {
+    /// The ranges of the input that contain classes, without the surrounding quotes. Ranges are
+    /// sorted and never overlap.
+    pub fn ranges(&self, input: &[u8]) -> Vec<Range<usize>> {
+        let len = input.len();
+        let mut ranges = vec![];
+        let mut cursor = cursor::Cursor::new(input);
+
+        while cursor.pos < len {
+            if !is_name(cursor.curr) || is_name(cursor.prev) {
+                cursor.advance();
+                continue;
+            }
+
+            let start = cursor.pos;
+            let end = input[start..]
+                .iter()
+                .position(|x| !is_name(*x))
+                .map_or(len, |offset| start + offset);
+            let name = &input[start..end];
+
+            cursor.move_to(skip_whitespace(input, end));
+
+            if self.attributes.iter().any(|x| x.as_bytes() == name)
+                && matches!(cursor.curr, b'=' | b':')
+            {
+                cursor.move_to(skip_whitespace(input, cursor.pos + 1));
+
+                match cursor.curr {
+                    // E.g.: `class="flex"`, or `className: "flex"`
+                    b'"' | b'\'' | b'`' => parse_string(&mut cursor, &mut ranges),
+
+                    // E.g.: `className={isActive ? 'flex' : 'hidden'}`
+                    b'{' => parse_expression(&mut cursor, &mut ranges),
+
+                    _ => {}
+                }
+            } else if self.functions.iter().any(|x| x.as_bytes() == name) {
+                match cursor.curr {
+                    // E.g.: `clsx('flex', isActive && 'underline')`
+                    b'(' => parse_expression(&mut cursor, &mut ranges),
+
+                    // E.g.: `` tw`flex` ``
+                    b'`' => parse_string(&mut cursor, &mut ranges),
+
+                    _ => {}
+                }
+            }
+        }
+
+        ranges
+    }
+
+    /// Replace everything outside of the class contexts with spaces. Line breaks are kept, so that
+    /// positions and line numbers don't change. CSS variables are kept as well, because they are
+    /// extracted everywhere, e.g. `--brand` in `style="color: var(--brand)"`.
+    pub fn mask(&self, input: &mut [u8]) {
+        let ranges = self.ranges(input);
+        let mut pos = 0usize;
+
+        for range in ranges
+            .iter()
+            .chain(std::iter::once(&(input.len()..input.len())))
+        {
+            let mut idx = pos;
+            let mut prev = pos.checked_sub(1).map_or(b' ', |x| input[x]);
+
+            while idx < range.start {
+                // E.g.: `--brand`, but not `a--b`
+                if !is_css_variable(prev) && input[idx..range.start].starts_with(b"--") {
+                    idx = input[idx..range.start]
+                        .iter()
+                        .position(|x| !is_css_variable(*x))
+                        .map_or(range.start, |offset| idx + offset);
+                    prev = b'-';
+                    continue;
+                }
+
+                prev = input[idx];
+                if input[idx] != b'\n' {
+                    input[idx] = b' ';
+                }
+
+                idx += 1;
+            }
+
+            pos = range.end;
+        }
+    }
+}
+
+/// Parse the string at the cursor, and move the cursor past it
+fn parse_string(cursor: &mut cursor::Cursor<'_>, ranges: &mut Vec<Range<usize>>) {
+    let start = cursor.pos;
+
+    match StringMachine::with_whitespace().next(cursor) {
+        MachineState::Done(span) => {
+            ranges.push(span.start + 1..span.end);
+            cursor.move_to(span.end + 1);
+        }
+
+        // Not a string after all (e.g. an apostrophe), continue right after the quote
+        MachineState::Idle => cursor.move_to(start + 1),
+    }
+}
+
+/// Parse the strings inside of the `{…}` or `(…)` at the cursor, and move the cursor past it
+fn parse_expression(cursor: &mut cursor::Cursor<'_>, ranges: &mut Vec<Range<usize>>) {
+    let len = cursor.input.len();
+    let mut depth = 0usize;
+
+    while cursor.pos < len {
+        match cursor.curr {
+            b'"' | b'\'' | b'`' => {
+                parse_string(cursor, ranges);
+                continue;
+            }
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    cursor.advance();
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        cursor.advance();
+    }
+}
+
+fn skip_whitespace(input: &[u8], pos: usize) -> usize {
+    input[pos.min(input.len())..]
+        .iter()
+        .position(|x| !x.is_ascii_whitespace())
+        .map_or(input.len(), |offset| pos + offset)
+}
+
+/// Characters of CSS variable names
+#[inline(always)]
+fn is_css_variable(x: u8) -> bool {
+    x.is_ascii_alphanumeric() || matches!(x, b'-' | b'_') || x >= 0x80
+}
+
+/// Characters of attribute and function names
+#[inline(always)]
+fn is_name(x: u8) -> bool {
+    x.is_ascii_alphanumeric() || matches!(x, b'-' | b'_' | b'$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClassContext;
+
+    fn contexts(input: &str) -> Vec<&str> {
+        ClassContext::default()
+            .ranges(input.as_bytes())
+            .into_iter()
+            .map(|range| &input[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_class_contexts() {
+        for (input, expected) in [
+            // HTML
+            (
+                r#"<div class="flex items-center" title="Some text">"#,
+                vec!["flex items-center"],
+            ),
+            (r#"<div class = 'flex'>"#, vec!["flex"]),
+            (r#"<div data-class="flex">"#, vec![]),
+            (
+                r#"<div :class="{ underline: isActive }">"#,
+                vec!["{ underline: isActive }"],
+            ),
+            // JSX
+            (r#"<div className="flex">"#, vec!["flex"]),
+            (
+                r#"<div className={isActive ? 'flex' : "hidden"}>"#,
+                vec!["flex", "hidden"],
+            ),
+            (
+                r#"<div className={`flex ${isActive ? 'underline' : ''}`}>"#,
+                vec!["flex ${isActive ? 'underline' : ''}"],
+            ),
+            // Compiled JavaScript
+            (
+                r#"jsx("div", { className: "flex p-4", children: "Hello world" })"#,
+                vec!["flex p-4"],
+            ),
+            // Helpers
+            (
+                r#"clsx('flex', { 'underline': isActive }, ['p-4'])"#,
+                vec!["flex", "underline", "p-4"],
+            ),
+            (
+                r#"const button = cva("px-4", { variants: { intent: { primary: "bg-blue-500" } } })"#,
+                vec!["px-4", "bg-blue-500"],
+            ),
+            (r#"utils.cn("flex", className)"#, vec!["flex"]),
+            (
+                "const title = tw`text-xl font-bold`",
+                vec!["text-xl font-bold"],
+            ),
+            // Unknown functions and prose
+            (r#"console.log("flex items-center")"#, vec![]),
+            ("Use the class attribute for classes", vec![]),
+            (r#"clsxx("flex")"#, vec![]),
+            // Multiple lines
+            (
+                "clsx(\n  'flex',\n  isActive && 'underline'\n)\n<p>flex</p>",
+                vec!["flex", "underline"],
+            ),
+        ] {
+            assert_eq!(contexts(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_configured_class_contexts() {
+        let mut context = ClassContext::default();
+        context.attributes.push("wrapperClass".into());
+        context.functions.push("classNames".into());
+
+        let input = r#"<Card wrapperClass="p-4" title="flex" /> classNames("underline")"#;
+        let actual: Vec<_> = context
+            .ranges(input.as_bytes())
+            .into_iter()
+            .map(|range| &input[range])
+            .collect();
+
+        assert_eq!(actual, vec!["p-4", "underline"]);
+    }
+
+    #[test]
+    fn test_mask() {
+        let mut input = b"<div class=\"flex\">\n  Some text\n</div>".to_vec();
+        ClassContext::default().mask(&mut input);
+
+        assert_eq!(input, b"            flex  \n           \n      ".to_vec());
+
+        // CSS variables are kept
+        let mut input = b"<p class=\"flex\" style=\"color: var(--brand)\">a--b</p>".to_vec();
+        ClassContext::default().mask(&mut input);
+
+        assert_eq!(
+            input,
+            b"          flex                    --brand           ".to_vec()
+        );
+    }
+}
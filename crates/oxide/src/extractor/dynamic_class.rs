@@ -0,0 +1,364 @@
+//! Find classes that are constructed at runtime, e.g.:
+//!
+//! ```text
+//! <div class={`bg-${color}-500`}>
+//!               ^^^^^^^^^^^^^^^  Interpolation
+//! <div class={'text-' + size}>
+//!               ^^^^^            Concatenation
+//! ```
+//!
+//! The extractor only sees the static fragments (`bg-`, `-500`, `text-`), which are not valid
+//! candidates, so the styles are silently missing. This is a heuristic: fragments are reported
+//! when they touch an interpolation (`${…}`, `#{…}`, `{{ … }}`, `{$…}`, `$name`, `<%= … %>` or
+//! `{…}`) or a concatenation operator (`+`, `.`, `~` or `<<`) next to a string.
+use crate::extractor::machine::Span;
+
+/// Maximum length of an interpolation, anything longer is most likely not an interpolation
+const MAX_INTERPOLATION_LEN: usize = 256;
+
+/// Extensions of files that can construct classes at runtime: JavaScript and TypeScript, PHP,
+/// Ruby and templates. Other files, e.g. CSS, Markdown or JSON, are not checked.
+const EXTENSIONS: &[&str] = &[
+    // JavaScript and TypeScript
+    "js",
+    "jsx",
+    "mjs",
+    "cjs",
+    "ts",
+    "tsx",
+    "mts",
+    "cts",
+    // PHP
+    "php",
+    // Ruby
+    "rb",
+    "erb",
+    "haml",
+    "slim",
+    // Templates
+    "html",
+    "htm",
+    "vue",
+    "svelte",
+    "astro",
+    "twig",
+    "njk",
+    "liquid",
+    "hbs",
+    "handlebars",
+    "mustache",
+    "heex",
+    "leex",
+    "eex",
+    "ex",
+    "exs",
+    "cshtml",
+    "razor",
+    "pug",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DynamicClassKind {
+    /// E.g.: `bg-${color}-500`
+    Interpolation,
+
+    /// E.g.: `'text-' + size`
+    Concatenation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicClass {
+    /// For interpolations the fragments together with the interpolation, e.g. `bg-${color}-500`.
+    /// For concatenations only the fragment inside of the string, e.g. `text-`.
+    pub span: Span,
+
+    pub kind: DynamicClassKind,
+}
+
+/// Whether files with the given compound extension are checked for dynamic classes, e.g.
+/// `tsx` or `blade.php`
+pub fn is_dynamic_class_extension(extension: &str) -> bool {
+    extension
+        .rsplit('.')
+        .next()
+        .is_some_and(|extension| EXTENSIONS.contains(&extension))
+}
+
+/// Find all classes in the input that are constructed at runtime, sorted by position.
+pub fn find_dynamic_classes(input: &[u8]) -> Vec<DynamicClass> {
+    let len = input.len();
+    let mut result = vec![];
+    let mut pos = 0;
+
+    while pos < len {
+        if let Some((open_len, close_end)) = parse_interpolation(input, pos) {
+            let prefix_start = fragment_start(input, pos);
+            let suffix_end = fragment_end(input, close_end);
+
+            let prefix = &input[prefix_start..pos];
+            let suffix = &input[close_end..suffix_end];
+
+            if is_dynamic_prefix(prefix) || is_dynamic_suffix(suffix) {
+                result.push(DynamicClass {
+                    span: Span::new(prefix_start, suffix_end - 1),
+                    kind: DynamicClassKind::Interpolation,
+                });
+            }
+
+            // Continue inside of the interpolation, it can contain concatenations as well
+            pos += open_len;
+            continue;
+        }
+
+        if matches!(input[pos], b'"' | b'\'' | b'`') {
+            // The string ends with a fragment and is followed by a concatenation
+            //
+            // E.g.: `'text-' + size`
+            //         ^^^^^
+            let start = fragment_start(input, pos);
+            if start > 0
+                && is_dynamic_prefix(&input[start..pos])
+                && matches!(input[start - 1], b'"' | b'\'' | b'`' | b' ' | b'\t')
+                && is_concatenation(skip_spaces(&input[pos + 1..]))
+            {
+                result.push(DynamicClass {
+                    span: Span::new(start, pos - 1),
+                    kind: DynamicClassKind::Concatenation,
+                });
+            }
+
+            // The string starts with a fragment and follows a concatenation
+            //
+            // E.g.: `size + '-500'`
+            //                ^^^^
+            let end = fragment_end(input, pos + 1);
+            if is_dynamic_suffix(&input[pos + 1..end])
+                && matches!(
+                    input.get(end),
+                    Some(b'"' | b'\'' | b'`' | b' ' | b'\t') | None
+                )
+                && follows_concatenation(&input[..pos])
+            {
+                result.push(DynamicClass {
+                    span: Span::new(pos + 1, end - 1),
+                    kind: DynamicClassKind::Concatenation,
+                });
+            }
+        }
+
+        pos += 1;
+    }
+
+    result.sort_unstable_by_key(|x| (x.span.start, x.span.end));
+    result.dedup();
+    result
+}
+
+/// Parse the interpolation starting at `pos`. Returns the length of its opening delimiter, and the
+/// end (exclusive) of its closing delimiter.
+fn parse_interpolation(input: &[u8], pos: usize) -> Option<(usize, usize)> {
+    let rest = &input[pos..];
+
+    let (open_len, close): (usize, &[u8]) = match rest {
+        [b'$' | b'#', b'{', ..] => (2, b"}"),
+        [b'{', b'{', ..] => (2, b"}}"),
+        [b'{', b'$' | b'a'..=b'z' | b'A'..=b'Z' | b'_', ..] => (1, b"}"),
+        [b'<', b'%', b'=', ..] => (3, b"%>"),
+        [b'<', b'?', b'=', ..] => (3, b"?>"),
+
+        // PHP variables inside of double quoted strings, e.g.: `"bg-$color"`
+        [b'$', b'a'..=b'z' | b'A'..=b'Z' | b'_', ..] => {
+            let end = rest[1..]
+                .iter()
+                .position(|x| !(x.is_ascii_alphanumeric() || *x == b'_'))
+                .map_or(input.len(), |offset| pos + 1 + offset);
+
+            return Some((1, end));
+        }
+
+        _ => return None,
+    };
+
+    let mut depth = 0usize;
+    let limit = input.len().min(pos + MAX_INTERPOLATION_LEN);
+
+    for i in pos + open_len..limit {
+        match input[i] {
+            b'{' => depth += 1,
+            b'}' if depth > 0 && close == b"}" => depth -= 1,
+            _ if depth == 0 && input[i..].starts_with(close) => {
+                return Some((open_len, i + close.len()));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Start of the fragment that ends right before `pos`
+fn fragment_start(input: &[u8], pos: usize) -> usize {
+    input[..pos]
+        .iter()
+        .rposition(|x| !is_fragment(*x))
+        .map_or(0, |idx| idx + 1)
+}
+
+/// End (exclusive) of the fragment that starts at `pos`
+fn fragment_end(input: &[u8], pos: usize) -> usize {
+    let pos = pos.min(input.len());
+    input[pos..]
+        .iter()
+        .position(|x| !is_fragment(*x))
+        .map_or(input.len(), |offset| pos + offset)
+}
+
+/// A fragment in front of a dynamic part, e.g. `bg-` in `bg-${color}`, or `hover:` in
+/// `hover:${classes}`
+fn is_dynamic_prefix(fragment: &[u8]) -> bool {
+    !is_css_variable(fragment)
+        && fragment.iter().any(u8::is_ascii_alphabetic)
+        && matches!(fragment.last(), Some(b'-' | b':' | b'/' | b'['))
+}
+
+/// A fragment after a dynamic part, e.g. `-500` in `${color}-500`
+fn is_dynamic_suffix(fragment: &[u8]) -> bool {
+    !is_css_variable(fragment)
+        && fragment.iter().any(u8::is_ascii_alphanumeric)
+        && matches!(fragment.first(), Some(b'-' | b'/'))
+}
+
+/// CSS variables are not candidates, e.g. `--color-` in `'--color-' + name`
+fn is_css_variable(fragment: &[u8]) -> bool {
+    fragment.starts_with(b"--")
+}
+
+fn is_concatenation(input: &[u8]) -> bool {
+    match input {
+        [b'<', b'<', ..] => true,
+        [b'+', next, ..] => !matches!(next, b'+' | b'='),
+        // E.g.: `'bg-' . $color`, but not member access like `'text-'.length`
+        [b'.', next, ..] => !next.is_ascii_alphanumeric() && !matches!(next, b'_' | b'.'),
+        [b'~', next, ..] => !next.is_ascii_digit() && *next != b'.',
+        _ => false,
+    }
+}
+
+fn follows_concatenation(input: &[u8]) -> bool {
+    let input = input.trim_ascii_end();
+
+    let before = match input {
+        [.., b'<', b'<'] => &input[..input.len() - 2],
+        [.., b'+' | b'.' | b'~'] => &input[..input.len() - 1],
+        _ => return false,
+    };
+
+    // There has to be an operand, e.g. a variable, a function call or another string
+    matches!(
+        before.trim_ascii_end().last(),
+        Some(x) if x.is_ascii_alphanumeric() || matches!(x, b'_' | b'$' | b')' | b']' | b'"' | b'\'' | b'`')
+    )
+}
+
+fn skip_spaces(input: &[u8]) -> &[u8] {
+    let start = input
+        .iter()
+        .position(|x| !matches!(x, b' ' | b'\t'))
+        .unwrap_or(input.len());
+    &input[start..]
+}
+
+/// Characters that can be part of a candidate
+#[inline(always)]
+fn is_fragment(x: u8) -> bool {
+    x.is_ascii_alphanumeric() || matches!(x, b'-' | b'_' | b':' | b'/' | b'[' | b']' | b'!' | b'@')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_dynamic_classes, DynamicClassKind};
+
+    fn find(input: &str) -> Vec<(&str, DynamicClassKind)> {
+        find_dynamic_classes(input.as_bytes())
+            .into_iter()
+            .map(|x| (&input[x.span.start..=x.span.end], x.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_interpolations() {
+        use DynamicClassKind::Interpolation;
+
+        for (input, expected) in [
+            // JavaScript
+            (
+                "<div className={`flex bg-${color}-500`}>",
+                vec!["bg-${color}-500"],
+            ),
+            ("`${base}-500 underline`", vec!["${base}-500"]),
+            ("`hover:${classes}`", vec!["hover:${classes}"]),
+            ("`w-[${width}px]`", vec!["w-[${width}px]"]),
+            (
+                "`bg-${isActive ? 'red' : 'blue'}-500`",
+                vec!["bg-${isActive ? 'red' : 'blue'}-500"],
+            ),
+            // Ruby
+            (r#"class: "text-#{size}""#, vec!["text-#{size}"]),
+            ("<div class=\"p-<%= padding %>\">", vec!["p-<%= padding %>"]),
+            // PHP
+            (r#"<div class="bg-{$color}-500">"#, vec!["bg-{$color}-500"]),
+            (r#"<div class="bg-$color-500">"#, vec!["bg-$color-500"]),
+            (
+                r#"<div class="text-<?= $size ?>">"#,
+                vec!["text-<?= $size ?>"],
+            ),
+            // Templates
+            (
+                r#"<div class="bg-{{ color }}-500">"#,
+                vec!["bg-{{ color }}-500"],
+            ),
+            (r#"<div class="bg-{color}">"#, vec!["bg-{color}"]),
+            // Static classes next to interpolations are fine
+            ("`flex ${classes} underline`", vec![]),
+            (r#"<div class="{{ classes }}">"#, vec![]),
+            ("`${prefix}-${name}`", vec![]),
+            ("const styles = { color: 'red' }", vec![]),
+            (r#"<div class="p-4 {$classes}">"#, vec![]),
+        ] {
+            let expected: Vec<_> = expected.into_iter().map(|x| (x, Interpolation)).collect();
+            assert_eq!(find(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_concatenations() {
+        use DynamicClassKind::Concatenation;
+
+        for (input, expected) in [
+            // JavaScript
+            ("className={'text-' + size}", vec!["text-"]),
+            ("className={\"flex text-\" + size}", vec!["text-"]),
+            ("className={size + '-500'}", vec!["-500"]),
+            ("className={'bg-' + color + '-500'}", vec!["bg-", "-500"]),
+            // PHP
+            ("<div class=\"<?php echo 'bg-' . $color ?>\">", vec!["bg-"]),
+            ("$classes = $color . '-500';", vec!["-500"]),
+            // Ruby
+            ("class: 'text-' << size", vec!["text-"]),
+            // Twig
+            ("{% set classes = 'bg-' ~ color %}", vec!["bg-"]),
+            // Static strings are fine
+            ("className={'flex ' + classes}", vec![]),
+            ("'--color-' + 1", vec![]),
+            ("size + '--x'", vec![]),
+            ("const total = count + '-'", vec![]),
+            ("'flex' + 'underline'", vec![]),
+            ("'text-'.length", vec![]),
+            ("'bg-'.$color", vec!["bg-"]),
+            ("x = 'a-' ++ b", vec![]),
+        ] {
+            let expected: Vec<_> = expected.into_iter().map(|x| (x, Concatenation)).collect();
+            assert_eq!(find(input), expected, "input: {input}");
+        }
+    }
+}
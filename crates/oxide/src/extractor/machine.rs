@@ -1,6 +1,6 @@
 use crate::cursor;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Span {
     /// Inclusive start position of the span
     pub start: usize,
@@ -137,25 +137,27 @@ pub trait Machine: Sized + Default {
         input
             // Mimicking the behavior of how we parse lines individually
             .split_terminator("\n")
-            .flat_map(|input| {
-                let mut machine = Self::default();
-                let mut cursor = cursor::Cursor::new(input.as_bytes());
+            .flat_map(|input| Self::test_extract_all_with(Self::default(), input))
+            .collect()
+    }
 
-                let mut actual: Vec<&str> = vec![];
-                let len = cursor.input.len();
+    /// Extract all spans with an already configured machine, e.g. one with a prefix. The input is
+    /// parsed as a whole, instead of line by line.
+    #[cfg(test)]
+    fn test_extract_all_with(mut machine: Self, input: &str) -> Vec<&str> {
+        let mut cursor = cursor::Cursor::new(input.as_bytes());
 
-                while cursor.pos < len {
-                    if let MachineState::Done(span) = machine.next(&mut cursor) {
-                        actual.push(unsafe {
-                            std::str::from_utf8_unchecked(span.slice(cursor.input))
-                        });
-                    }
+        let mut actual: Vec<&str> = vec![];
+        let len = cursor.input.len();
 
-                    cursor.advance();
-                }
+        while cursor.pos < len {
+            if let MachineState::Done(span) = machine.next(&mut cursor) {
+                actual.push(unsafe { std::str::from_utf8_unchecked(span.slice(cursor.input)) });
+            }
 
-                actual
-            })
-            .collect()
+            cursor.advance();
+        }
+
+        actual
     }
 }
@@ -1,8 +1,11 @@
 use crate::cursor;
 use crate::extractor::machine::Span;
+use crate::fast_skip::{fast_skip, fast_skip_to_css_variable};
 use candidate_machine::CandidateMachine;
+use class_context::ClassContext;
 use css_variable_machine::CssVariableMachine;
 use machine::{Machine, MachineState};
+use separator::Separator;
 use std::fmt;
 
 pub mod arbitrary_property_machine;
@@ -11,12 +14,16 @@ pub mod arbitrary_variable_machine;
 mod boundary;
 pub mod bracket_stack;
 pub mod candidate_machine;
+pub mod candidate_parser;
+pub mod class_context;
 pub mod css_variable_machine;
+pub mod dynamic_class;
 pub mod machine;
 pub mod modifier_machine;
 pub mod named_utility_machine;
 pub mod named_variant_machine;
 pub mod pre_processors;
+pub mod separator;
 pub mod string_machine;
 pub mod utility_machine;
 pub mod variant_machine;
@@ -53,12 +60,26 @@ impl fmt::Display for Extracted<'_> {
     }
 }
 
+/// Options that change which candidates are extracted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractorOptions {
+    /// Only extract candidates that use this prefix as their first variant, e.g. `tw` for
+    /// `tw:flex`. CSS variables are always extracted.
+    pub prefix: Option<String>,
+
+    /// Separates variants from each other and from the utility, `:` by default.
+    pub separator: Separator,
+}
+
 #[derive(Debug)]
 pub struct Extractor<'a> {
     cursor: cursor::Cursor<'a>,
 
     css_variable_machine: CssVariableMachine,
     candidate_machine: CandidateMachine,
+
+    /// Only extract candidates inside of these ranges, see [`Extractor::with_class_context`]
+    class_contexts: Option<Vec<std::ops::Range<usize>>>,
 }
 
 impl<'a> Extractor<'a> {
@@ -68,7 +89,55 @@ impl<'a> Extractor<'a> {
 
             css_variable_machine: Default::default(),
             candidate_machine: Default::default(),
+
+            class_contexts: None,
+        }
+    }
+
+    pub fn with_options(input: &'a [u8], options: &ExtractorOptions) -> Self {
+        let mut extractor = Self::new(input);
+
+        if !options.separator.is_default() {
+            extractor = extractor.with_separator(options.separator);
+        }
+
+        if let Some(prefix) = &options.prefix {
+            extractor = extractor.with_prefix(prefix);
         }
+
+        extractor
+    }
+
+    /// Only extract candidates that use `prefix` as their first variant. A trailing `:` is
+    /// optional, so both `tw` and `tw:` are accepted.
+    ///
+    /// E.g.: with a prefix of `tw`, `tw:flex` and `tw:hover:flex` are extracted, but `flex` and
+    /// `hover:tw:flex` are not.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.strip_suffix(':').unwrap_or(prefix);
+        if !prefix.is_empty() {
+            self.candidate_machine =
+                std::mem::take(&mut self.candidate_machine).with_prefix(prefix.as_bytes());
+        }
+
+        self
+    }
+
+    /// Use a custom separator between variants, e.g. `__` for `hover__flex`. The extracted
+    /// candidates keep the separator, use [`normalize_separator`] to get the `hover:flex` form.
+    ///
+    /// [`normalize_separator`]: candidate_parser::normalize_separator
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.candidate_machine =
+            std::mem::take(&mut self.candidate_machine).with_separator(separator);
+        self
+    }
+
+    /// Only extract candidates inside of class contexts, e.g. `class="…"` attributes or `clsx(…)`
+    /// calls. CSS variables are still extracted everywhere.
+    pub fn with_class_context(mut self, class_context: &ClassContext) -> Self {
+        self.class_contexts = Some(class_context.ranges(self.cursor.input));
+        self
     }
 
     pub fn extract(&mut self) -> Vec<Extracted<'a>> {
@@ -86,9 +155,10 @@ impl<'a> Extractor<'a> {
         {
             let cursor = &mut self.cursor.clone();
             while cursor.pos < len {
-                if cursor.curr.is_ascii_whitespace() {
-                    cursor.advance();
-                    continue;
+                // CSS variables always start with `--`, so we can jump straight to the next one
+                match fast_skip_to_css_variable(cursor) {
+                    Some(pos) => cursor.move_to(pos),
+                    None => break,
                 }
 
                 if let MachineState::Done(span) = self.css_variable_machine.next(cursor) {
@@ -101,30 +171,50 @@ impl<'a> Extractor<'a> {
 
         // Candidate extractor
         {
-            let cursor = &mut self.cursor.clone();
-
-            while cursor.pos < len {
-                if cursor.curr.is_ascii_whitespace() {
-                    cursor.advance();
-                    continue;
-                }
-
-                let before = cursor.pos;
-                match self.candidate_machine.next(cursor) {
-                    MachineState::Done(span) => {
-                        in_flight_spans.push(span);
-                        extract_sub_candidates(before..span.start, cursor, &mut in_flight_spans);
+            let everything = 0..len;
+            let ranges = self
+                .class_contexts
+                .as_deref()
+                .unwrap_or(std::slice::from_ref(&everything));
+
+            for range in ranges {
+                // Candidates can't continue past the end of the range
+                let cursor = &mut cursor::Cursor::new(&self.cursor.input[..range.end]);
+                cursor.move_to(range.start);
+                self.candidate_machine.reset();
+
+                while cursor.pos < range.end {
+                    if cursor.curr.is_ascii_whitespace() {
+                        match fast_skip(cursor) {
+                            Some(pos) => cursor.move_to(pos),
+                            None => cursor.advance(),
+                        }
+                        continue;
                     }
-                    MachineState::Idle => {
-                        extract_sub_candidates(
-                            before..cursor.pos.min(cursor.input.len()),
-                            cursor,
-                            &mut in_flight_spans,
-                        );
+
+                    let before = cursor.pos;
+                    match self.candidate_machine.next(cursor) {
+                        MachineState::Done(span) => {
+                            in_flight_spans.push(span);
+                            extract_sub_candidates(
+                                before..span.start,
+                                cursor,
+                                &self.candidate_machine,
+                                &mut in_flight_spans,
+                            );
+                        }
+                        MachineState::Idle => {
+                            extract_sub_candidates(
+                                before..cursor.pos.min(cursor.input.len()),
+                                cursor,
+                                &self.candidate_machine,
+                                &mut in_flight_spans,
+                            );
+                        }
                     }
-                }
 
-                cursor.advance();
+                    cursor.advance();
+                }
             }
 
             // Commit the remaining in-flight spans as extracted candidates
@@ -154,6 +244,7 @@ impl<'a> Extractor<'a> {
 fn extract_sub_candidates(
     range: std::ops::Range<usize>,
     cursor: &cursor::Cursor<'_>,
+    candidate_machine: &CandidateMachine,
     in_flight_spans: &mut Vec<Span>,
 ) {
     let end = range.end;
@@ -162,7 +253,7 @@ fn extract_sub_candidates(
             let mut cursor = cursor.clone();
             cursor.move_to(i + 1);
 
-            let mut machine = CandidateMachine::default();
+            let mut machine = candidate_machine.fresh();
 
             while cursor.pos < end {
                 if let MachineState::Done(span) = machine.next(&mut cursor) {
@@ -197,7 +288,7 @@ fn drop_covered_spans(mut spans: Vec<Span>) -> Vec<Span> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Extracted, Extractor};
+    use super::{ClassContext, Extracted, Extractor, Separator};
     use crate::throughput::Throughput;
     use std::hint::black_box;
 
@@ -1019,6 +1110,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_candidates_after_skipped_characters() {
+        for (input, expected) in [
+            // Punctuation is not skipped, it still decides where candidates can start
+            ("x=(flex)", vec![]),
+            ("{underline}", vec![]),
+            ("a>b", vec!["b"]),
+            ("\"flex\":'p-4';`m-2`", vec!["flex", "p-4", "m-2"]),
+            // Long runs of whitespace are skipped in one go
+            ("                                flex", vec!["flex"]),
+            (
+                "\n\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\tflex      ",
+                vec!["flex"],
+            ),
+            (
+                "flex                                underline                    p-4",
+                vec!["flex", "underline", "p-4"],
+            ),
+        ] {
+            assert_extract_sorted_candidates(input, expected);
+        }
+
+        // CSS variables are found by jumping to the next `--`
+        for (input, expected) in [
+            ("x=(var(--brand))", vec!["--brand"]),
+            ("a--b --c", vec!["--b", "--c"]),
+            ("                                --brand", vec!["--brand"]),
+            ("flex underline", vec![]),
+        ] {
+            assert_extract_sorted_css_variables(input, expected);
+        }
+    }
+
     #[test]
     fn test_extract_css_variables() {
         for (input, expected) in [
@@ -1085,4 +1209,92 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_extract_with_prefix() {
+        for prefix in ["tw", "tw:"] {
+            let input = r#"<div class="tw:flex tw:hover:underline flex hover:tw:flex tw:[&_p]:mt-2 tw:[--gap:4px]" style="gap: var(--tw-gap)"></div>"#;
+
+            let mut actual = Extractor::new(input.as_bytes())
+                .with_prefix(prefix)
+                .extract()
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>();
+            actual.sort();
+
+            assert_eq!(
+                actual,
+                vec![
+                    "Candidate(tw:[&_p]:mt-2)",
+                    "Candidate(tw:[--gap:4px])",
+                    "Candidate(tw:flex)",
+                    "Candidate(tw:hover:underline)",
+                    "CssVariable(--gap)",
+                    "CssVariable(--tw-gap)",
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_with_prefix_js() {
+        let input = r#"const classes = { 'tw:flex': isActive, tw: true }; cn(["tw:p-4", size])"#;
+
+        let mut actual = Extractor::new(input.as_bytes())
+            .with_prefix("tw")
+            .extract()
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        actual.sort();
+
+        assert_eq!(actual, vec!["Candidate(tw:flex)", "Candidate(tw:p-4)"]);
+    }
+
+    #[test]
+    fn test_extract_with_separator() {
+        let input =
+            r#"<div class="md__hover__flex [&_p]__underline hover:p-4 grid-cols-[1fr_auto]">"#;
+
+        let mut actual = Extractor::new(input.as_bytes())
+            .with_separator(Separator::new("__").unwrap())
+            .extract()
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        actual.sort();
+
+        assert_eq!(
+            actual,
+            vec![
+                "Candidate([&_p]__underline)",
+                "Candidate(class)",
+                "Candidate(grid-cols-[1fr_auto])",
+                "Candidate(md__hover__flex)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_with_class_context() {
+        let input = r#"<p class="flex items-center" style="color: var(--brand)">Read the docs for underline and flex-col</p>"#;
+
+        let mut actual = Extractor::new(input.as_bytes())
+            .with_class_context(&ClassContext::default())
+            .extract()
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        actual.sort();
+
+        assert_eq!(
+            actual,
+            vec![
+                "Candidate(flex)",
+                "Candidate(items-center)",
+                "CssVariable(--brand)",
+            ]
+        );
+    }
 }
@@ -3,9 +3,10 @@ use crate::extractor::arbitrary_value_machine::ArbitraryValueMachine;
 use crate::extractor::arbitrary_variable_machine::ArbitraryVariableMachine;
 use crate::extractor::machine::{Machine, MachineState};
 use crate::extractor::modifier_machine::ModifierMachine;
+use crate::extractor::separator::Separator;
 use classification_macros::ClassifyBytes;
 
-/// Extract named variants from an input including the `:`.
+/// Extract named variants from an input including the `:` (or a custom [`Separator`]).
 ///
 /// E.g.:
 ///
@@ -27,6 +28,9 @@ pub struct NamedVariantMachine {
     /// Current state of the machine
     state: State,
 
+    /// Marks the end of the variant
+    separator: Separator,
+
     arbitrary_variable_machine: ArbitraryVariableMachine,
     arbitrary_value_machine: ArbitraryValueMachine,
     modifier_machine: ModifierMachine,
@@ -75,17 +79,17 @@ impl Machine for NamedVariantMachine {
 
         match self.state {
             State::Idle => match cursor.curr.into() {
-                Class::AlphaLower | Class::Star => match cursor.next.into() {
+                Class::AlphaLower | Class::Star => {
                     // Valid single character variant, must be followed by a `:`
                     //
                     // E.g.: `<div class="x:flex"></div>`
                     //                    ^^
                     // E.g.: `*:`
                     //        ^^
-                    Class::Colon => {
+                    if self.separator.is_at(cursor.input, cursor.pos + 1) {
                         self.state = State::ParseEnd;
                         cursor.advance();
-                        self.next(cursor)
+                        return self.next(cursor);
                     }
 
                     // Valid start characters
@@ -94,13 +98,11 @@ impl Machine for NamedVariantMachine {
                     //        ^
                     // E.g.: `**:`
                     //        ^
-                    _ => {
-                        self.start_pos = cursor.pos;
-                        self.state = State::Parsing;
-                        cursor.advance();
-                        self.next(cursor)
-                    }
-                },
+                    self.start_pos = cursor.pos;
+                    self.state = State::Parsing;
+                    cursor.advance();
+                    self.next(cursor)
+                }
 
                 // Valid start characters
                 //
@@ -121,6 +123,15 @@ impl Machine for NamedVariantMachine {
 
             State::Parsing => {
                 while cursor.pos < len {
+                    // The separator means we are at the end of the variant
+                    //
+                    // E.g.: `hover:`
+                    //             ^
+                    if self.separator.is_at(cursor.input, cursor.pos) {
+                        cursor.advance_by(self.separator.len() - 1);
+                        return self.done(self.start_pos, cursor);
+                    }
+
                     match cursor.curr.into() {
                         Class::Dash => match cursor.next.into() {
                             // Start of an arbitrary value
@@ -220,12 +231,6 @@ impl Machine for NamedVariantMachine {
                             return self.next(cursor);
                         }
 
-                        // A `:` means we are at the end of the variant
-                        //
-                        // E.g.: `hover:`
-                        //             ^
-                        Class::Colon => return self.done(self.start_pos, cursor),
-
                         // Everything else is invalid
                         _ => return self.restart(),
                     };
@@ -236,37 +241,61 @@ impl Machine for NamedVariantMachine {
 
             State::ParsingModifier => match self.modifier_machine.next(cursor) {
                 MachineState::Idle => self.restart(),
-                MachineState::Done(_) => match cursor.next.into() {
-                    // Modifier must be followed by a `:`
-                    //
-                    // E.g.: `group-hover/name:`
-                    //                        ^
-                    Class::Colon => {
-                        self.state = State::ParseEnd;
-                        cursor.advance();
-                        self.next(cursor)
-                    }
-
-                    // Everything else is invalid
-                    _ => self.restart(),
-                },
-            },
+                // Modifier must be followed by a `:`
+                //
+                // E.g.: `group-hover/name:`
+                //                        ^
+                MachineState::Done(_) if self.separator.is_at(cursor.input, cursor.pos + 1) => {
+                    self.state = State::ParseEnd;
+                    cursor.advance();
+                    self.next(cursor)
+                }
 
-            State::ParseEnd => match cursor.curr.into() {
-                // The end of a variant must be the `:`
+                // A custom separator can be part of a modifier, in that case the modifier ends
+                // right before it.
                 //
-                // E.g.: `hover:`
-                //             ^
-                Class::Colon => self.done(self.start_pos, cursor),
+                // E.g.: `group-hover/name__flex`
+                //                    ^^^^^^^^^^  Modifier
+                //                        ^^      Separator
+                MachineState::Done(span) => {
+                    match (span.start + 1..=span.end)
+                        .find(|pos| self.separator.is_at(cursor.input, *pos))
+                    {
+                        Some(pos) => {
+                            self.state = State::ParseEnd;
+                            cursor.move_to(pos);
+                            self.next(cursor)
+                        }
 
-                // Everything else is invalid
-                _ => self.restart(),
+                        // Everything else is invalid
+                        None => self.restart(),
+                    }
+                }
             },
+
+            // The end of a variant must be the `:`
+            //
+            // E.g.: `hover:`
+            //             ^
+            State::ParseEnd if self.separator.is_at(cursor.input, cursor.pos) => {
+                cursor.advance_by(self.separator.len() - 1);
+                self.done(self.start_pos, cursor)
+            }
+
+            // Everything else is invalid
+            State::ParseEnd => self.restart(),
         }
     }
 }
 
 impl NamedVariantMachine {
+    pub fn with_separator(separator: Separator) -> Self {
+        Self {
+            separator,
+            ..Default::default()
+        }
+    }
+
     #[inline(always)]
     fn parse_arbitrary_end(&mut self, cursor: &mut cursor::Cursor<'_>) -> MachineState {
         match cursor.next.into() {
@@ -275,7 +304,7 @@ impl NamedVariantMachine {
                 cursor.advance();
                 self.next(cursor)
             }
-            Class::Colon => {
+            _ if self.separator.is_at(cursor.input, cursor.pos + 1) => {
                 self.state = State::ParseEnd;
                 cursor.advance();
                 self.next(cursor)
@@ -0,0 +1,215 @@
+use crate::cursor;
+use crate::extractor::pre_processors::expression::{
+    attribute_value_start, normalize_bracketed, normalize_expression, skip_whitespace,
+};
+use crate::extractor::pre_processors::pre_processor::PreProcessor;
+use bstr::ByteSlice;
+
+/// Attributes whose values are expressions that evaluate to classes
+const CLASS_BINDINGS: &[&[u8]] = &[b"[ngClass]", b"[class]", b"[className]"];
+
+/// Host objects are small, a `host: {` without a closing brace nearby isn't one
+const MAX_HOST_LENGTH: usize = 4096;
+
+#[derive(Debug, Default)]
+pub struct Angular {
+    /// Only pre-process content with a `@Component(` or `@Directive(` decorator, e.g. for
+    /// TypeScript files that aren't components or directives
+    pub decorated_only: bool,
+}
+
+impl PreProcessor for Angular {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        if self.decorated_only && !has_decorator(content) {
+            return content.to_vec();
+        }
+
+        let len = content.len();
+        let mut result = content.to_vec();
+        let mut cursor = cursor::Cursor::new(content);
+
+        while cursor.pos < len {
+            let rest = &content[cursor.pos..];
+
+            match cursor.curr {
+                // Class bindings, e.g.: `[class.bg-red-500]="isActive"`, also as the key of a
+                // host binding, e.g.: `host: { '[class.bg-red-500]': 'isActive' }`
+                b'[' if rest.starts_with(b"[class.") => {
+                    if let Some(end) = class_binding_end(content, cursor.pos + b"[class.".len()) {
+                        result[cursor.pos..cursor.pos + b"[class.".len()].fill(b' ');
+                        result[end] = b' ';
+                    }
+                }
+
+                // E.g.: `[ngClass]="{ 'bg-red-500': isActive }"`, or `[class]="isActive ? 'flex' : ''"`
+                b'[' if is_attribute_start(cursor.prev) => {
+                    if let Some(start) = CLASS_BINDINGS
+                        .iter()
+                        .find(|name| rest.starts_with(name))
+                        .and_then(|name| attribute_value_start(content, cursor.pos + name.len()))
+                    {
+                        normalize_expression(content, &mut result, start, Some(content[start - 1]));
+                    }
+                }
+
+                // Host bindings of components and directives, e.g.:
+                // `host: { class: 'flex', '[class.underline]': 'isActive' }`
+                b'h' if rest.starts_with(b"host") && !is_name(cursor.prev) => {
+                    let pos = skip_whitespace(content, cursor.pos + b"host".len());
+                    if content.get(pos) == Some(&b':') {
+                        let pos = skip_whitespace(content, pos + 1);
+                        if content.get(pos) == Some(&b'{') {
+                            normalize_bracketed(content, &mut result, pos, MAX_HOST_LENGTH);
+                        }
+                    }
+                }
+
+                // E.g.: `@HostBinding('class.bg-red-500')`
+                b'@' if rest.starts_with(b"@HostBinding(") => {
+                    let pos = skip_whitespace(content, cursor.pos + b"@HostBinding(".len());
+                    if matches!(content.get(pos), Some(b'\'' | b'"' | b'`'))
+                        && content[pos + 1..].starts_with(b"class.")
+                    {
+                        result[pos + 1..pos + 1 + b"class.".len()].fill(b' ');
+                    }
+                }
+
+                _ => {}
+            }
+
+            cursor.advance();
+        }
+
+        result
+    }
+}
+
+/// Position of the `]` that closes a `[class.…]` binding, the class itself can contain brackets,
+/// e.g.: `[class.bg-[#0088cc]]`
+fn class_binding_end(content: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for (idx, x) in content.iter().enumerate().skip(start) {
+        match x {
+            b'[' => depth += 1,
+            b']' if depth == 0 => return (idx > start).then_some(idx),
+            b']' => depth -= 1,
+            b' ' | b'\t' | b'\n' | b'\r' | b'"' | b'\'' | b'=' | b'>' => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn has_decorator(content: &[u8]) -> bool {
+    content.contains_str("@Component(") || content.contains_str("@Directive(")
+}
+
+#[inline(always)]
+fn is_attribute_start(x: u8) -> bool {
+    x.is_ascii_whitespace() || x == b'\0'
+}
+
+#[inline(always)]
+fn is_name(x: u8) -> bool {
+    x.is_ascii_alphanumeric() || matches!(x, b'_' | b'$' | b'-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Angular, MAX_HOST_LENGTH};
+    use crate::extractor::pre_processors::pre_processor::PreProcessor;
+
+    #[test]
+    fn test_angular_pre_processor() {
+        for (input, expected) in [
+            // `ngClass` with the object syntax
+            (
+                r#"<div [ngClass]="{ 'flex underline': isActive, 'p-4': x }">"#,
+                r#"<div [ngClass]="  'flex underline'  isActive  'p-4'  x  ">"#,
+            ),
+            (
+                r#"<div [ngClass]="{underline:isActive}">"#,
+                r#"<div [ngClass]=" underline isActive ">"#,
+            ),
+            // `ngClass` with the array syntax
+            (
+                r#"<div [ngClass]="['p-2', isBig ? 'text-xl' : 'text-sm']">"#,
+                r#"<div [ngClass]=" 'p-2'  isBig   'text-xl'   'text-sm' ">"#,
+            ),
+            // Class bindings
+            (
+                r#"<div [class]="isActive ? 'bg-red-500' : ''">"#,
+                r#"<div [class]="isActive   'bg-red-500'   ''">"#,
+            ),
+            (
+                r#"<div [class.bg-red-500]="isActive" [class.hover:underline]="x">"#,
+                r#"<div        bg-red-500 ="isActive"        hover:underline ="x">"#,
+            ),
+            (
+                r#"<div [class.bg-[#0088cc]]="x">"#,
+                r#"<div        bg-[#0088cc] ="x">"#,
+            ),
+            // Other bindings are kept as-is
+            (
+                r#"<div [style]="{ color: x }" [class.]="x">"#,
+                r#"<div [style]="{ color: x }" [class.]="x">"#,
+            ),
+            // Host bindings
+            (
+                "host: { class: 'flex', '[class.p-4]': 'isBig' },",
+                "host:   class  'flex'  '       p-4 '  'isBig'  ,",
+            ),
+            (
+                "@HostBinding('class.bg-red-500') isActive = true;",
+                "@HostBinding('      bg-red-500') isActive = true;",
+            ),
+            // Other properties are kept as-is
+            ("const ghost = { x: 'y' }", "const ghost = { x: 'y' }"),
+            // Unbalanced host objects are kept as-is
+            (
+                "host: { class: 'flex'\nconst x = a ? { b: 1 } : c;",
+                "host: { class: 'flex'\nconst x = a ? { b: 1 } : c;",
+            ),
+        ] {
+            Angular::test(input, expected);
+        }
+    }
+
+    #[test]
+    fn test_angular_long_host_object() {
+        let input = format!(
+            "host: {{ class: 'flex', {} }}",
+            "'[attr.x]': 'y', ".repeat(MAX_HOST_LENGTH / 16)
+        );
+
+        // The closing brace is too far away
+        Angular::test(&input, &input);
+    }
+
+    #[test]
+    fn test_angular_extraction() {
+        Angular::test_extract_contains(
+            r#"
+                @Component({
+                  selector: 'app-card',
+                  host: { class: 'block rounded-lg', '[class.shadow-md]': 'raised' },
+                  template: `<div [ngClass]="{underline:isActive,'bg-red-500 text-white':hasError}"></div>`,
+                })
+                export class CardComponent {
+                  @HostBinding('class.p-4') padded = true;
+                }
+            "#,
+            vec![
+                "block",
+                "rounded-lg",
+                "shadow-md",
+                "underline",
+                "bg-red-500",
+                "text-white",
+                "p-4",
+            ],
+        );
+    }
+}
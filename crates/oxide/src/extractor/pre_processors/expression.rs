@@ -0,0 +1,112 @@
+//! Helpers for pre-processors of templates with JavaScript expressions in their attributes, e.g.
+//! `:class="{ 'bg-red-500': isActive }"` in Vue or `[ngClass]="{ underline: x }"` in Angular.
+
+/// Position right after the opening quote of an attribute value, e.g. `:class = "…"`. `pos` is
+/// the end of the attribute name.
+pub fn attribute_value_start(content: &[u8], pos: usize) -> Option<usize> {
+    let pos = skip_whitespace(content, pos);
+    if content.get(pos) != Some(&b'=') {
+        return None;
+    }
+
+    let pos = skip_whitespace(content, pos + 1);
+    match content.get(pos) {
+        Some(b'"' | b'\'') => Some(pos + 1),
+        _ => None,
+    }
+}
+
+/// Replace the punctuation of object and array literals, ternaries and boolean operators with
+/// spaces, so that only strings and identifiers remain. Strings are kept as-is.
+///
+/// E.g.: `{ underline: isActive, 'p-4': x }` → `  underline  isActive  'p-4'  x  `
+///
+/// With a `quote`, the expression is an attribute value that ends at that quote, and the
+/// position of the quote is returned. Otherwise the expression starts at a bracket, and the
+/// position right after the matching closing bracket is returned.
+pub fn normalize_expression(
+    content: &[u8],
+    result: &mut [u8],
+    start: usize,
+    quote: Option<u8>,
+) -> usize {
+    normalize(content, result, start, quote).unwrap_or(content.len())
+}
+
+/// Like `normalize_expression` for an expression that starts at a bracket, but nothing is
+/// replaced unless the matching closing bracket is within the first `max_len` bytes. This keeps
+/// unbalanced brackets from affecting everything up to the end of the file.
+pub fn normalize_bracketed(
+    content: &[u8],
+    result: &mut [u8],
+    start: usize,
+    max_len: usize,
+) -> Option<usize> {
+    let end = start.saturating_add(max_len).min(content.len());
+    let mut expression = result[start..end].to_vec();
+    let len = normalize(&content[start..end], &mut expression, 0, None)?;
+
+    result[start..start + len].copy_from_slice(&expression[..len]);
+    Some(start + len)
+}
+
+/// The end of the expression, or `None` if it starts at a bracket that isn't closed
+fn normalize(content: &[u8], result: &mut [u8], start: usize, quote: Option<u8>) -> Option<usize> {
+    let len = content.len();
+    let mut depth = 0usize;
+    let mut pos = start;
+
+    while pos < len && Some(content[pos]) != quote {
+        match content[pos] {
+            // Strings inside of attributes use the other quotes, e.g.: `"{ 'flex': x }"`
+            b'"' | b'\'' | b'`' => {
+                let end_char = content[pos];
+                pos += 1;
+
+                while pos < len && content[pos] != end_char && Some(content[pos]) != quote {
+                    if content[pos] == b'\\' {
+                        pos += 1;
+                    }
+
+                    pos += 1;
+                }
+
+                // Unterminated string, the attribute ends here
+                if pos < len && content[pos] != end_char {
+                    break;
+                }
+            }
+
+            b'{' | b'[' | b'(' => {
+                depth += 1;
+                result[pos] = b' ';
+            }
+
+            b'}' | b']' | b')' => {
+                result[pos] = b' ';
+                depth = depth.saturating_sub(1);
+
+                if depth == 0 && quote.is_none() {
+                    return Some(pos + 1);
+                }
+            }
+
+            b',' | b':' | b'?' | b'!' | b'&' | b'|' => {
+                result[pos] = b' ';
+            }
+
+            _ => {}
+        }
+
+        pos += 1;
+    }
+
+    quote.map(|_| pos.min(len))
+}
+
+pub fn skip_whitespace(content: &[u8], pos: usize) -> usize {
+    content[pos.min(content.len())..]
+        .iter()
+        .position(|x| !x.is_ascii_whitespace())
+        .map_or(content.len(), |offset| pos + offset)
+}
@@ -0,0 +1,198 @@
+use crate::cursor;
+use crate::extractor::pre_processors::expression::{normalize_expression, skip_whitespace};
+use crate::extractor::pre_processors::pre_processor::PreProcessor;
+use bstr::ByteSlice;
+
+#[derive(Debug, Default)]
+pub struct Heex;
+
+impl PreProcessor for Heex {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        let len = content.len();
+        let mut result = content.to_vec();
+        let mut cursor = cursor::Cursor::new(content);
+
+        while cursor.pos < len {
+            match cursor.curr {
+                // HEEx templates embedded in Elixir source, e.g.: `~H"""<div class="flex">"""`
+                b'~' if cursor.next == b'H' => {
+                    if let Some(start) = parse_sigil(content, &mut result, cursor.pos) {
+                        cursor.move_to(start);
+                        continue;
+                    }
+                }
+
+                // Expressions in class and `phx-` attributes, e.g.:
+                // `class={["px-2", @active && "bg-blue-500"]}`, or
+                // `phx-click={JS.add_class("bg-red-500") |> JS.remove_class("opacity-0")}`
+                b'a'..=b'z' if cursor.prev.is_ascii_whitespace() => {
+                    let name_end = content[cursor.pos..]
+                        .iter()
+                        .position(|x| !is_attribute_name(*x))
+                        .map_or(len, |offset| cursor.pos + offset);
+                    let name = &content[cursor.pos..name_end];
+
+                    if is_class_attribute(name) && content.get(name_end) == Some(&b'=') {
+                        let start = skip_whitespace(content, name_end + 1);
+                        if content.get(start) == Some(&b'{') {
+                            let end = normalize_expression(content, &mut result, start, None);
+                            cursor.move_to(end);
+                            continue;
+                        }
+                    }
+
+                    cursor.move_to(name_end);
+                    continue;
+                }
+
+                _ => {}
+            }
+
+            cursor.advance();
+        }
+
+        result
+    }
+}
+
+/// Replace the delimiters of the sigil at `pos` with spaces, and return the start of its
+/// contents. Heredocs (`~H"""…"""`) and single line sigils (`~H"…"`, `~H(…)`, …) are supported.
+fn parse_sigil(content: &[u8], result: &mut [u8], pos: usize) -> Option<usize> {
+    let rest = &content[pos + 2..];
+
+    let (open, close): (&[u8], &[u8]) = if rest.starts_with(b"\"\"\"") {
+        (b"\"\"\"", b"\"\"\"")
+    } else if rest.starts_with(b"'''") {
+        (b"'''", b"'''")
+    } else {
+        match rest.first()? {
+            b'"' => (b"\"", b"\""),
+            b'\'' => (b"'", b"'"),
+            b'(' => (b"(", b")"),
+            b'[' => (b"[", b"]"),
+            b'{' => (b"{", b"}"),
+            b'<' => (b"<", b">"),
+            b'|' => (b"|", b"|"),
+            b'/' => (b"/", b"/"),
+            _ => return None,
+        }
+    };
+
+    let start = pos + 2 + open.len();
+    let end = start + content[start..].find(close)?;
+
+    result[pos..start].fill(b' ');
+    result[end..end + close.len()].fill(b' ');
+
+    Some(start)
+}
+
+/// Attributes that contain classes, or `phx-` attributes with JS commands that add or remove
+/// classes, e.g. `class`, `active_class` or `phx-click`
+fn is_class_attribute(name: &[u8]) -> bool {
+    name.starts_with(b"phx-") || name == b"class" || name.ends_with(b"_class")
+}
+
+#[inline(always)]
+fn is_attribute_name(x: u8) -> bool {
+    x.is_ascii_alphanumeric() || matches!(x, b'-' | b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Heex;
+    use crate::extractor::pre_processors::pre_processor::PreProcessor;
+
+    #[test]
+    fn test_heex_pre_processor() {
+        for (input, expected) in [
+            // List syntax
+            (
+                r#"<div class={["px-2", @active && "bg-blue-500"]}>"#,
+                r#"<div class=  "px-2"  @active    "bg-blue-500"  >"#,
+            ),
+            (
+                r#"<div class={["px-2",@active&&"bg-blue-500"]}>"#,
+                r#"<div class=  "px-2" @active  "bg-blue-500"  >"#,
+            ),
+            (
+                r#"<div class={["p-4", if(@big, do: "text-xl", else: "text-sm")]}>"#,
+                r#"<div class=  "p-4"  if @big  do  "text-xl"  else  "text-sm"   >"#,
+            ),
+            // Strings are kept as-is
+            (
+                r#"<div class={"p-4 md:flex"}>"#,
+                r#"<div class= "p-4 md:flex" >"#,
+            ),
+            // Function components
+            (
+                r#"<.link navigate={~p"/"} class="underline hover:text-blue-500">Home</.link>"#,
+                r#"<.link navigate={~p"/"} class="underline hover:text-blue-500">Home</.link>"#,
+            ),
+            (
+                r#"<.button active_class={["ring-2", @x && "ring-blue-500"]}>"#,
+                r#"<.button active_class=  "ring-2"  @x    "ring-blue-500"  >"#,
+            ),
+            // `phx-` attributes
+            (
+                r##"<button phx-click={JS.add_class("bg-red-500", to: "#x") |> JS.remove_class("opacity-0")}>"##,
+                r##"<button phx-click= JS.add_class "bg-red-500"  to  "#x"   > JS.remove_class "opacity-0"  >"##,
+            ),
+            (
+                r#"<div phx-mounted={JS.transition({"ease-out duration-300", "opacity-0"})}>"#,
+                r#"<div phx-mounted= JS.transition  "ease-out duration-300"  "opacity-0"   >"#,
+            ),
+            // Other attributes are kept as-is
+            (
+                r#"<div id={"user-#{@id}"} style={[color: "red"]}>"#,
+                r#"<div id={"user-#{@id}"} style={[color: "red"]}>"#,
+            ),
+            // Sigils
+            (
+                "~H\"\"\"\n<div class={[\"flex\", @x && \"p-4\"]}>\n\"\"\"",
+                "     \n<div class=  \"flex\"  @x    \"p-4\"  >\n   ",
+            ),
+            (
+                r#"~H"<span class='flex'></span>""#,
+                r#"   <span class='flex'></span> "#,
+            ),
+            (
+                "~H(<span class=\"flex\"></span>)",
+                "   <span class=\"flex\"></span> ",
+            ),
+        ] {
+            Heex::test(input, expected);
+        }
+    }
+
+    #[test]
+    fn test_heex_extraction() {
+        Heex::test_extract_contains(
+            r##"
+                defmodule AppWeb.Components do
+                  use Phoenix.Component
+
+                  def card(assigns) do
+                    ~H"""
+                    <div class={["rounded-lg",@active&&"ring-2",@big && "p-8"]}>
+                      <.link navigate={~p"/"} class="underline hover:text-blue-500">Home</.link>
+                      <button phx-click={JS.toggle_class("hidden",to: "#menu")} class="phx-click-loading:opacity-50">
+                        Menu
+                      </button>
+                    </div>
+                    """
+                  end
+                end
+            "##,
+            vec![
+                "rounded-lg",
+                "ring-2",
+                "p-8",
+                "underline",
+                "hover:text-blue-500",
+                "hidden",
+                "phx-click-loading:opacity-50",
+            ],
+        );
+    }
+}
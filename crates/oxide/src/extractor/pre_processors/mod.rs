@@ -1,15 +1,24 @@
+pub mod angular;
+mod expression;
 pub mod haml;
+pub mod heex;
 pub mod pre_processor;
 pub mod pug;
 pub mod razor;
+pub mod registry;
 pub mod ruby;
 pub mod slim;
 pub mod svelte;
+pub mod vue;
 
+pub use angular::*;
 pub use haml::*;
+pub use heex::*;
 pub use pre_processor::*;
 pub use pug::*;
 pub use razor::*;
+pub use registry::*;
 pub use ruby::*;
 pub use slim::*;
 pub use svelte::*;
+pub use vue::*;
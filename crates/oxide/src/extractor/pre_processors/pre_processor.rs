@@ -1,8 +1,14 @@
-pub trait PreProcessor: Sized + Default {
+/// Rewrites content before it's extracted, e.g. to turn template syntax into something the
+/// extractor understands. The output has to have the exact same length as the input, so that
+/// positions stay the same.
+pub trait PreProcessor: Send + Sync {
     fn process(&self, content: &[u8]) -> Vec<u8>;
 
     #[cfg(test)]
-    fn test(input: &str, expected: &str) {
+    fn test(input: &str, expected: &str)
+    where
+        Self: Sized + Default,
+    {
         let input = input.as_bytes();
         let expected = expected.as_bytes();
 
@@ -27,7 +33,10 @@ pub trait PreProcessor: Sized + Default {
     }
 
     #[cfg(test)]
-    fn test_extract_contains(input: &str, items: Vec<&str>) {
+    fn test_extract_contains(input: &str, items: Vec<&str>)
+    where
+        Self: Sized + Default,
+    {
         use crate::extractor::{Extracted, Extractor};
 
         let input = input.as_bytes();
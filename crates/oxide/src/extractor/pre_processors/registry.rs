@@ -0,0 +1,413 @@
+//! Decide which pre-processors run on a file, e.g.:
+//!
+//! ```text
+//! jbuilder            → Ruby
+//! html.slim           → Slim
+//! app/views/**/*.html → Ruby, Slim
+//! ```
+//!
+//! Patterns without glob characters are extensions. Globs without a `/` are matched against the
+//! file name, other globs against the path.
+//!
+//! Globs are checked first, in order, and the first match wins. Otherwise the extensions of the
+//! file are checked from the most specific to the least specific one, so `index.html.erb` checks
+//! `html.erb` and then `erb`. This matters for files like `show.html.erb` (Rails),
+//! `view.blade.php` (Laravel) or `counter.svelte.ts` (Svelte 5 runes), where the last extension
+//! alone doesn't tell what the file contains.
+use crate::extractor::pre_processors::*;
+use fast_glob::glob_match;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+static DEFAULT_REGISTRY: LazyLock<PreProcessorRegistry> =
+    LazyLock::new(PreProcessorRegistry::default);
+
+/// Names of the built-in pre-processors, in the order they are checked by default
+const BUILTINS: &[(&str, &[&str])] = &[
+    ("angular", &["component.html", "ts"]),
+    ("razor", &["cshtml", "razor"]),
+    ("haml", &["haml"]),
+    ("heex", &["heex", "leex", "eex", "ex", "exs"]),
+    ("pug", &["pug"]),
+    ("ruby", &["rb", "erb"]),
+    ("slim", &["slim"]),
+    ("svelte", &["svelte"]),
+    ("vue", &["vue"]),
+];
+
+#[derive(Clone)]
+pub struct PreProcessorRegistry {
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    pattern: FilePattern,
+
+    /// Pre-processors that run one after the other, each on the output of the previous one
+    chain: Vec<Arc<dyn PreProcessor>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilePattern {
+    /// E.g.: `jbuilder` or `html.slim`
+    Extension(String),
+
+    /// E.g.: `*.html.slim`, matched against the file name
+    FileGlob(String),
+
+    /// E.g.: `app/views/**/*.html`, matched against the path
+    PathGlob(String),
+}
+
+impl FilePattern {
+    fn new(pattern: &str) -> Self {
+        if !pattern.contains(['*', '?', '[', '{', '/']) {
+            let extension = pattern.trim_start_matches('.');
+            return FilePattern::Extension(extension.to_owned());
+        }
+
+        if !pattern.contains('/') {
+            return FilePattern::FileGlob(pattern.to_owned());
+        }
+
+        // Relative globs can match anywhere in the path
+        match pattern.strip_prefix("./") {
+            Some(pattern) => FilePattern::PathGlob(format!("**/{pattern}")),
+            None if pattern.starts_with('/') || pattern.starts_with("**") => {
+                FilePattern::PathGlob(pattern.to_owned())
+            }
+            None => FilePattern::PathGlob(format!("**/{pattern}")),
+        }
+    }
+
+    /// Whether the glob matches the file, content without a path never matches
+    fn matches_path(&self, path: &Path) -> bool {
+        match self {
+            FilePattern::Extension(_) => false,
+            FilePattern::FileGlob(pattern) => path
+                .file_name()
+                .is_some_and(|name| glob_match(pattern, name.as_encoded_bytes())),
+            FilePattern::PathGlob(pattern) => {
+                let path = path.to_string_lossy().replace('\\', "/");
+                glob_match(pattern, path.as_bytes())
+            }
+        }
+    }
+}
+
+/// All extensions of a file, e.g. `html.erb` for `show.html.erb`. Leading dots of hidden files
+/// are ignored, so `.eslintrc.json` has an extension of `json`.
+pub fn compound_extension(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .and_then(|name| {
+            name.trim_start_matches('.')
+                .split_once('.')
+                .map(|(_, extension)| extension.to_owned())
+        })
+        .unwrap_or_default()
+}
+
+/// The extension and its less specific suffixes, e.g. `html.erb` and `erb`
+fn extension_suffixes(extension: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(extension), |extension| {
+        extension.split_once('.').map(|(_, rest)| rest)
+    })
+    .filter(|extension| !extension.is_empty())
+}
+
+impl PreProcessorRegistry {
+    /// A registry without any pre-processors, not even the built-in ones
+    pub fn empty() -> Self {
+        Self { entries: vec![] }
+    }
+
+    /// The built-in pre-processor with the given name: `angular`, `razor`, `haml`, `heex`, `pug`,
+    /// `ruby`, `slim`, `svelte` or `vue`.
+    pub fn builtin(name: &str) -> Option<Arc<dyn PreProcessor>> {
+        Some(match name {
+            "angular" => Arc::new(Angular::default()),
+            "razor" => Arc::new(Razor),
+            "haml" => Arc::new(Haml),
+            "heex" => Arc::new(Heex),
+            "pug" => Arc::new(Pug),
+            "ruby" => Arc::new(Ruby),
+            "slim" => Arc::new(Slim),
+            "svelte" => Arc::new(Svelte),
+            "vue" => Arc::new(Vue),
+            _ => return None,
+        })
+    }
+
+    /// Run `chain` on all files matching `pattern`. Registered patterns take precedence over
+    /// the ones registered before, including the built-in ones. An empty chain disables
+    /// pre-processing for matching files.
+    pub fn register(&mut self, pattern: &str, chain: Vec<Arc<dyn PreProcessor>>) {
+        self.entries.insert(
+            0,
+            Entry {
+                pattern: FilePattern::new(pattern),
+                chain,
+            },
+        );
+    }
+
+    /// The pre-processors for a file, or for content with the given extension if there is no
+    /// path. The extension can be a compound extension, e.g. `html.erb`. For files the
+    /// extensions are taken from the file name instead, unless it doesn't have any.
+    pub fn find(&self, path: Option<&Path>, extension: &str) -> &[Arc<dyn PreProcessor>] {
+        if let Some(path) = path {
+            if let Some(entry) = self
+                .entries
+                .iter()
+                .find(|entry| entry.pattern.matches_path(path))
+            {
+                return &entry.chain;
+            }
+        }
+
+        let file_extension = path.map(compound_extension).unwrap_or_default();
+        let extension = match file_extension.is_empty() {
+            true => extension,
+            false => &file_extension,
+        };
+
+        let entry = extension_suffixes(extension).find_map(|suffix| {
+            self.entries.iter().find(|entry| {
+                matches!(&entry.pattern, FilePattern::Extension(pattern) if pattern == suffix)
+            })
+        });
+
+        entry
+            .map(|entry| entry.chain.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn process(&self, content: &[u8], path: Option<&Path>, extension: &str) -> Vec<u8> {
+        let mut chain = self.find(path, extension).iter();
+
+        let Some(first) = chain.next() else {
+            return content.to_vec();
+        };
+
+        chain.fold(first.process(content), |content, pre_processor| {
+            pre_processor.process(&content)
+        })
+    }
+
+    /// The registry with only the built-in pre-processors, shared between all callers
+    pub(crate) fn shared() -> &'static Self {
+        &DEFAULT_REGISTRY
+    }
+}
+
+impl Default for PreProcessorRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        for (name, extensions) in BUILTINS.iter().rev() {
+            for extension in extensions.iter().rev() {
+                let pre_processor: Arc<dyn PreProcessor> = match (*name, *extension) {
+                    // Only components and directives, e.g. not `counter.svelte.ts`
+                    ("angular", "ts") => Arc::new(Angular {
+                        decorated_only: true,
+                    }),
+                    _ => Self::builtin(name).unwrap(),
+                };
+
+                registry.register(extension, vec![pre_processor]);
+            }
+        }
+
+        registry
+    }
+}
+
+impl fmt::Debug for PreProcessorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.entries
+                    .iter()
+                    .map(|entry| (&entry.pattern, entry.chain.len())),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compound_extension, PreProcessorRegistry};
+    use std::path::Path;
+
+    fn process(registry: &PreProcessorRegistry, path: &str, input: &str) -> String {
+        let extension = Path::new(path)
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy();
+
+        let actual = registry.process(input.as_bytes(), Some(Path::new(path)), &extension);
+        assert_eq!(actual.len(), input.len());
+
+        String::from_utf8(actual).unwrap()
+    }
+
+    #[test]
+    fn test_default_registry() {
+        let registry = PreProcessorRegistry::default();
+
+        // Ruby
+        assert_eq!(
+            process(&registry, "/app/views/index.html.erb", "%w[flex underline]"),
+            "%w flex underline "
+        );
+
+        // Unknown extensions are left alone
+        assert_eq!(
+            process(&registry, "/app/views/index.jbuilder", "%w[flex underline]"),
+            "%w[flex underline]"
+        );
+
+        // Content without a path
+        assert_eq!(
+            String::from_utf8(registry.process(b"%w[flex]", None, "rb")).unwrap(),
+            "%w flex "
+        );
+
+        // Angular only runs on TypeScript files with components or directives
+        for (name, expected) in [
+            ("Component", "@Component({ host:   class  'flex'   })"),
+            ("Directive", "@Directive({ host:   class  'flex'   })"),
+        ] {
+            let input = format!("@{name}({{ host: {{ class: 'flex' }} }})");
+            for path in [
+                "/src/app/card.component.ts",
+                "/src/app/tooltip.directive.ts",
+            ] {
+                assert_eq!(process(&registry, path, &input), expected);
+            }
+        }
+        for path in ["/src/app/utils.ts", "/src/lib/counter.svelte.ts"] {
+            assert_eq!(
+                process(&registry, path, "host: { class: 'flex' }"),
+                "host: { class: 'flex' }"
+            );
+        }
+        assert_eq!(
+            process(
+                &registry,
+                "/src/app/card.component.html",
+                "<div [class.flex]=\"x\">"
+            ),
+            "<div        flex =\"x\">"
+        );
+    }
+
+    #[test]
+    fn test_registered_patterns() {
+        let mut registry = PreProcessorRegistry::default();
+        let ruby = PreProcessorRegistry::builtin("ruby").unwrap();
+        let slim = PreProcessorRegistry::builtin("slim").unwrap();
+
+        registry.register(".jbuilder", vec![ruby.clone()]);
+        registry.register("*.txt.slim", vec![]);
+        registry.register("app/views/**/*.html", vec![ruby, slim]);
+
+        assert_eq!(
+            process(&registry, "/app/views/index.jbuilder", "%w[flex underline]"),
+            "%w flex underline "
+        );
+
+        // Matched by the file name glob, which disables the Slim pre-processor
+        assert_eq!(
+            process(&registry, "/app/views/index.txt.slim", ".flex.underline"),
+            ".flex.underline"
+        );
+        assert_eq!(
+            process(&registry, "/app/views/index.html.slim", ".flex.underline"),
+            " flex underline"
+        );
+
+        // Matched by the path glob, both pre-processors run
+        assert_eq!(
+            process(&registry, "/app/views/users/index.html", "%w[flex] .p-4"),
+            "%w flex   p-4"
+        );
+        assert_eq!(
+            process(&registry, "/public/index.html", "%w[flex] .p-4"),
+            "%w[flex] .p-4"
+        );
+    }
+
+    #[test]
+    fn test_compound_extension() {
+        for (path, expected) in [
+            ("/app/views/show.html.erb", "html.erb"),
+            ("/resources/views/welcome.blade.php", "blade.php"),
+            ("/src/lib/counter.svelte.ts", "svelte.ts"),
+            ("/src/index.html", "html"),
+            ("/.eslintrc.json", "json"),
+            ("/Dockerfile", ""),
+        ] {
+            assert_eq!(
+                compound_extension(Path::new(path)),
+                expected,
+                "path: {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compound_extensions() {
+        let mut registry = PreProcessorRegistry::default();
+        let ruby = PreProcessorRegistry::builtin("ruby").unwrap();
+
+        // The most specific extension wins, regardless of the order of registration
+        registry.register("blade.php", vec![ruby]);
+        registry.register("php", vec![]);
+        registry.register("txt.erb", vec![]);
+
+        assert_eq!(
+            process(&registry, "/views/welcome.blade.php", "%w[flex]"),
+            "%w flex "
+        );
+        assert_eq!(
+            process(&registry, "/views/welcome.php", "%w[flex]"),
+            "%w[flex]"
+        );
+        assert_eq!(
+            process(&registry, "/views/show.html.erb", "%w[flex]"),
+            "%w flex "
+        );
+        assert_eq!(
+            process(&registry, "/views/notes.txt.erb", "%w[flex]"),
+            "%w[flex]"
+        );
+
+        // Svelte 5 rune files are modules, not components
+        assert_eq!(
+            process(
+                &registry,
+                "/src/counter.svelte.ts",
+                "const c = 'class:flex'"
+            ),
+            "const c = 'class:flex'"
+        );
+        assert_eq!(
+            process(&registry, "/src/Counter.svelte", "<div class:flex>"),
+            "<div class flex>"
+        );
+
+        // Content without a path uses the extension as is
+        assert_eq!(
+            String::from_utf8(registry.process(b"%w[flex]", None, "html.erb")).unwrap(),
+            "%w flex "
+        );
+        assert_eq!(
+            String::from_utf8(registry.process(b"%w[flex]", None, "txt.erb")).unwrap(),
+            "%w[flex]"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::cursor;
+use crate::extractor::pre_processors::expression::{attribute_value_start, normalize_expression};
+use crate::extractor::pre_processors::pre_processor::PreProcessor;
+use bstr::ByteSlice;
+
+#[derive(Debug, Default)]
+pub struct Vue;
+
+impl PreProcessor for Vue {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        let len = content.len();
+        let mut result = content.to_vec();
+        let mut cursor = cursor::Cursor::new(content);
+
+        while cursor.pos < len {
+            match cursor.curr {
+                // Class bindings, e.g.: `:class="{ 'bg-red-500': isActive }"`, or
+                // `v-bind:class="[isActive ? 'p-2' : 'p-4']"`
+                b':' if is_class_binding(content, cursor.pos) => {
+                    if let Some(start) =
+                        attribute_value_start(content, cursor.pos + b":class".len())
+                    {
+                        let end = normalize_expression(
+                            content,
+                            &mut result,
+                            start,
+                            Some(content[start - 1]),
+                        );
+                        cursor.move_to(end);
+                        continue;
+                    }
+                }
+
+                // Style blocks, e.g.: `<style scoped> .btn { @apply px-4 hover:bg-blue-500; }`
+                b'<' if content[cursor.pos..].starts_with(b"<style") => {
+                    let end = content[cursor.pos..]
+                        .find(b"</style")
+                        .map_or(len, |offset| cursor.pos + offset);
+
+                    normalize_style(content, &mut result, cursor.pos, end);
+                    cursor.move_to(end);
+                    continue;
+                }
+
+                _ => {}
+            }
+
+            cursor.advance();
+        }
+
+        result
+    }
+}
+
+/// Whether `:class` at `pos` is a binding: `:class` or `v-bind:class` as an attribute name
+fn is_class_binding(content: &[u8], pos: usize) -> bool {
+    if !content[pos..].starts_with(b":class") {
+        return false;
+    }
+
+    // E.g.: `:classes`
+    if !matches!(
+        content.get(pos + b":class".len()),
+        Some(b'=' | b' ' | b'\t' | b'\n' | b'\r')
+    ) {
+        return false;
+    }
+
+    let before = &content[..pos];
+    let before = before.strip_suffix(b"v-bind").unwrap_or(before);
+
+    matches!(before.last(), Some(b' ' | b'\t' | b'\n' | b'\r'))
+}
+
+/// Replace the `;` at the end of `@apply` rules, e.g.: `@apply px-4 hover:bg-blue-500;`
+fn normalize_style(content: &[u8], result: &mut [u8], start: usize, end: usize) {
+    let mut pos = start;
+
+    while let Some(offset) = content[pos..end].find(b"@apply") {
+        pos += offset + b"@apply".len();
+
+        if let Some(offset) = content[pos..end]
+            .iter()
+            .position(|x| matches!(x, b';' | b'}' | b'\n'))
+        {
+            pos += offset;
+
+            if content[pos] == b';' {
+                result[pos] = b' ';
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vue;
+    use crate::extractor::pre_processors::pre_processor::PreProcessor;
+
+    #[test]
+    fn test_vue_pre_processor() {
+        for (input, expected) in [
+            // Object syntax
+            (
+                r#"<div :class="{ 'bg-red-500': isActive, underline: hasError }">"#,
+                r#"<div :class="  'bg-red-500'  isActive  underline  hasError  ">"#,
+            ),
+            (
+                r#"<div :class="{underline:isActive}">"#,
+                r#"<div :class=" underline isActive ">"#,
+            ),
+            // Array syntax
+            (
+                r#"<div v-bind:class="[isActive ? 'p-2' : 'p-4', hasError && 'm-1']">"#,
+                r#"<div v-bind:class=" isActive   'p-2'   'p-4'  hasError    'm-1' ">"#,
+            ),
+            (
+                r#"<div :class="[{ 'text-xl': big }, 'font-bold']">"#,
+                r#"<div :class="   'text-xl'  big    'font-bold' ">"#,
+            ),
+            // Single quoted attributes
+            (
+                r#"<div :class='{ "md:flex": x }'>"#,
+                r#"<div :class='  "md:flex"  x  '>"#,
+            ),
+            // Strings are kept as-is
+            (
+                r#"<div :class="`flex [&_p]:p-4 ${x ? 'a' : 'b'}`">"#,
+                r#"<div :class="`flex [&_p]:p-4 ${x ? 'a' : 'b'}`">"#,
+            ),
+            // Everything after the attribute is kept as-is
+            (
+                r#"<div :class="{ flex: x }" @click="toggle(!open)">"#,
+                r#"<div :class="  flex  x  " @click="toggle(!open)">"#,
+            ),
+            // Other attributes are kept as-is
+            (
+                r#"<div :classes="{ flex: x }" :style="{ color: x }">"#,
+                r#"<div :classes="{ flex: x }" :style="{ color: x }">"#,
+            ),
+            // `@apply` in style blocks
+            (
+                "<style scoped>\n.btn { @apply px-4 hover:bg-blue-500; color: red; }\n</style>",
+                "<style scoped>\n.btn { @apply px-4 hover:bg-blue-500  color: red; }\n</style>",
+            ),
+            (
+                "<style>.a { @apply flex }</style>",
+                "<style>.a { @apply flex }</style>",
+            ),
+        ] {
+            Vue::test(input, expected);
+        }
+    }
+
+    #[test]
+    fn test_vue_extraction() {
+        Vue::test_extract_contains(
+            r#"
+                <template>
+                  <div :class="{underline:isActive,'bg-red-500':hasError}"></div>
+                  <div v-bind:class="[isActive?'p-2':'p-4']"></div>
+                </template>
+
+                <style scoped>
+                .btn {
+                  @apply px-4 py-2 hover:bg-blue-500;
+                }
+                </style>
+            "#,
+            vec![
+                "underline",
+                "bg-red-500",
+                "p-2",
+                "p-4",
+                "px-4",
+                "py-2",
+                "hover:bg-blue-500",
+            ],
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+/// Separates variants from each other and from the utility, `:` by default.
+///
+/// E.g.: `hover:focus:flex`, or `hover__focus__flex` with a separator of `__`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Separator {
+    bytes: [u8; Separator::MAX_LEN],
+    len: usize,
+}
+
+impl Separator {
+    pub const MAX_LEN: usize = 4;
+
+    /// Returns `None` if the separator can't be distinguished from the rest of a candidate. A
+    /// separator can only consist of `:`, `_`, `|`, `~` and `^`.
+    pub fn new(separator: &str) -> Option<Self> {
+        let len = separator.len();
+        if len == 0 || len > Self::MAX_LEN {
+            return None;
+        }
+
+        if !separator
+            .bytes()
+            .all(|x| matches!(x, b':' | b'_' | b'|' | b'~' | b'^'))
+        {
+            return None;
+        }
+
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..len].copy_from_slice(separator.as_bytes());
+
+        Some(Self { bytes, len })
+    }
+
+    #[inline(always)]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    #[inline(always)]
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this is the default `:` separator
+    #[inline(always)]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the separator starts at `pos`
+    #[inline(always)]
+    pub fn is_at(&self, input: &[u8], pos: usize) -> bool {
+        // Most positions don't even start with the separator, so check the first byte on its own
+        input.get(pos) == Some(&self.bytes[0])
+            && (self.len == 1
+                || input
+                    .get(pos..pos + self.len)
+                    .is_some_and(|x| x == self.as_bytes()))
+    }
+}
+
+impl Default for Separator {
+    fn default() -> Self {
+        Self {
+            bytes: [b':', 0, 0, 0],
+            len: 1,
+        }
+    }
+}
+
+impl fmt::Debug for Separator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Separator({:?})",
+            String::from_utf8_lossy(self.as_bytes())
+        )
+    }
+}
+
+impl fmt::Display for Separator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(self.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Separator;
+
+    #[test]
+    fn test_separator() {
+        for (input, valid) in [
+            (":", true),
+            ("_", true),
+            ("__", true),
+            ("::", true),
+            ("", false),
+            ("-", false),
+            ("a", false),
+            (" ", false),
+            (".", false),
+            ("[", false),
+            ("_____", false),
+        ] {
+            assert_eq!(Separator::new(input).is_some(), valid, "input: {input}");
+        }
+
+        assert!(Separator::new(":").unwrap().is_default());
+        assert!(Separator::new("__").unwrap().is_at(b"hover__flex", 5));
+        assert!(!Separator::new("__").unwrap().is_at(b"hover_flex", 5));
+    }
+}
@@ -7,7 +7,8 @@ use classification_macros::ClassifyBytes;
 /// Rules:
 ///
 /// - The string must start and end with the same quote character.
-/// - The string cannot contain any whitespace characters.
+/// - The string cannot contain any whitespace characters, unless created with
+///   [`StringMachine::with_whitespace`].
 /// - The string can contain any other character except for the quote character (unless it's escaped).
 /// - Balancing of brackets is not required.
 ///
@@ -22,7 +23,9 @@ use classification_macros::ClassifyBytes;
 ///          ^^^^^^^^^^^^^
 /// ```
 #[derive(Debug, Default)]
-pub struct StringMachine;
+pub struct StringMachine {
+    allow_whitespace: bool,
+}
 
 impl Machine for StringMachine {
     #[inline(always)]
@@ -45,7 +48,7 @@ impl Machine for StringMachine {
             match cursor.curr.into() {
                 Class::Escape => match cursor.next.into() {
                     // An escaped whitespace character is not allowed
-                    Class::Whitespace => return MachineState::Idle,
+                    Class::Whitespace if !self.allow_whitespace => return MachineState::Idle,
 
                     // An escaped character, skip ahead to the next character
                     _ => cursor.advance(),
@@ -55,7 +58,7 @@ impl Machine for StringMachine {
                 Class::Quote if cursor.curr == end_char => return self.done(start_pos, cursor),
 
                 // Any kind of whitespace is not allowed
-                Class::Whitespace => return MachineState::Idle,
+                Class::Whitespace if !self.allow_whitespace => return MachineState::Idle,
 
                 // Everything else is valid
                 _ => {}
@@ -68,6 +71,15 @@ impl Machine for StringMachine {
     }
 }
 
+impl StringMachine {
+    /// Also accept strings containing whitespace, e.g. the value of a `class` attribute.
+    pub fn with_whitespace() -> Self {
+        Self {
+            allow_whitespace: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ClassifyBytes)]
 enum Class {
     #[bytes(b'"', b'\'', b'`')]
@@ -134,4 +146,21 @@ mod tests {
             assert_eq!(StringMachine::test_extract_all(input), expected);
         }
     }
+
+    #[test]
+    fn test_string_machine_with_whitespace_extraction() {
+        for (input, expected) in [
+            ("'foo'", vec!["'foo'"]),
+            ("' hello world '", vec!["' hello world '"]),
+            (r#""flex items-center""#, vec![r#""flex items-center""#]),
+            ("`flex\n  underline`", vec!["`flex\n  underline`"]),
+            (r#"'escaped\ whitespace'"#, vec![r#"'escaped\ whitespace'"#]),
+            ("'unfinished quote", vec![]),
+        ] {
+            let actual =
+                StringMachine::test_extract_all_with(StringMachine::with_whitespace(), input);
+
+            assert_eq!(actual, expected, "input: {input}");
+        }
+    }
 }
@@ -2,12 +2,14 @@ use crate::cursor;
 use crate::extractor::arbitrary_value_machine::ArbitraryValueMachine;
 use crate::extractor::machine::{Machine, MachineState};
 use crate::extractor::named_variant_machine::NamedVariantMachine;
+use crate::extractor::separator::Separator;
 use classification_macros::ClassifyBytes;
 
 #[derive(Debug, Default)]
 pub struct VariantMachine {
     arbitrary_value_machine: ArbitraryValueMachine,
     named_variant_machine: NamedVariantMachine,
+    separator: Separator,
 }
 
 impl Machine for VariantMachine {
@@ -42,25 +44,31 @@ impl Machine for VariantMachine {
 }
 
 impl VariantMachine {
+    pub fn with_separator(separator: Separator) -> Self {
+        Self {
+            named_variant_machine: NamedVariantMachine::with_separator(separator),
+            separator,
+            ..Default::default()
+        }
+    }
+
     #[inline(always)]
     fn parse_arbitrary_end(
         &mut self,
         start_pos: usize,
         cursor: &mut cursor::Cursor<'_>,
     ) -> MachineState {
-        match cursor.next.into() {
-            // End of an arbitrary value, must be followed by a `:`
-            //
-            // E.g.: `[&:hover]:`
-            //                 ^
-            Class::Colon => {
-                cursor.advance();
-                self.done(start_pos, cursor)
-            }
-
-            // Everything else is invalid
-            _ => self.restart(),
+        // End of an arbitrary value, must be followed by a `:`
+        //
+        // E.g.: `[&:hover]:`
+        //                 ^
+        if self.separator.is_at(cursor.input, cursor.pos + 1) {
+            cursor.advance_by(self.separator.len());
+            return self.done(start_pos, cursor);
         }
+
+        // Everything else is invalid
+        self.restart()
     }
 }
 
@@ -69,9 +77,6 @@ enum Class {
     #[bytes(b'[')]
     OpenBracket,
 
-    #[bytes(b':')]
-    Colon,
-
     #[fallback]
     Other,
 }
@@ -1,39 +1,60 @@
 use crate::cursor::Cursor;
+use bstr::ByteSlice;
 
 const STRIDE: usize = 16;
 type Mask = [bool; STRIDE];
 
+/// Skip a run of whitespace. Returns the position of the first non-whitespace character, or `None`
+/// if the cursor is not at whitespace or too close to the end of the input to be worth it.
 #[inline(always)]
 pub fn fast_skip(cursor: &Cursor) -> Option<usize> {
-    // If we don't have enough bytes left to check then bail early
-    if cursor.pos + STRIDE >= cursor.input.len() {
-        return None;
+    skip_while(cursor, is_ascii_whitespace)
+}
+
+/// Position of the next `--` at or after the cursor, which is the only way a CSS variable can
+/// start. Returns `None` if there are no CSS variables left in the input.
+#[inline(always)]
+pub fn fast_skip_to_css_variable(cursor: &Cursor) -> Option<usize> {
+    if cursor.curr == b'-' && cursor.next == b'-' {
+        return Some(cursor.pos);
     }
 
-    if !cursor.curr.is_ascii_whitespace() {
+    cursor.input[cursor.pos..]
+        .find(b"--")
+        .map(|offset| cursor.pos + offset)
+}
+
+#[inline(always)]
+fn skip_while(cursor: &Cursor, is_match: impl Fn([u8; STRIDE]) -> Mask) -> Option<usize> {
+    // If we don't have enough bytes left to check then bail early
+    if cursor.pos + STRIDE >= cursor.input.len() {
         return None;
     }
 
-    let mut offset = 1;
-
     // SAFETY: We've already checked (indirectly) that this index is valid
     let remaining = unsafe { cursor.input.get_unchecked(cursor.pos..) };
 
+    let mut offset = 0;
+
     // NOTE: This loop uses primitives designed to be auto-vectorized
     // Do not change this loop without benchmarking the results
     // And checking the generated assembly using godbolt.org
-    for (i, chunk) in remaining.chunks_exact(STRIDE).enumerate() {
-        let value = load(chunk);
-        let is_whitespace = is_ascii_whitespace(value);
-        let is_all_whitespace = all_true(is_whitespace);
+    for chunk in remaining.chunks_exact(STRIDE) {
+        let matches = is_match(load(chunk));
 
-        if is_all_whitespace {
-            offset = (i + 1) * STRIDE;
+        if all_true(matches) {
+            offset += STRIDE;
         } else {
+            // Only the leading matches can be skipped
+            offset += matches.iter().position(|x| !x).unwrap_or(STRIDE);
             break;
         }
     }
 
+    if offset == 0 {
+        return None;
+    }
+
     Some(cursor.pos + offset)
 }
 
@@ -72,7 +93,7 @@ fn all_true(a: [bool; STRIDE]) -> bool {
 }
 
 #[inline(always)]
-fn is_ascii_whitespace(value: [u8; STRIDE]) -> [bool; STRIDE] {
+fn is_ascii_whitespace(value: [u8; STRIDE]) -> Mask {
     let whitespace_1 = eq(value, b'\t');
     let whitespace_2 = eq(value, b'\n');
     let whitespace_3 = eq(value, b'\x0C');
@@ -87,3 +108,46 @@ fn is_ascii_whitespace(value: [u8; STRIDE]) -> [bool; STRIDE] {
         whitespace_5,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{fast_skip, fast_skip_to_css_variable};
+    use crate::cursor::Cursor;
+
+    #[test]
+    fn test_fast_skip() {
+        for (input, expected) in [
+            // Not at whitespace
+            ("flex                    underline", None),
+            // Too close to the end
+            ("    flex", None),
+            // Exact end of the run, also within a chunk
+            ("     flex                                ", Some(5)),
+            ("                      flex               ", Some(22)),
+            // Punctuation is not whitespace
+            (r#""                    flex"#, None),
+        ] {
+            assert_eq!(
+                fast_skip(&Cursor::new(input.as_bytes())),
+                expected,
+                "input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fast_skip_to_css_variable() {
+        for (input, expected) in [
+            ("--foo", Some(0)),
+            ("color: var(--foo)", Some(11)),
+            ("-mt-4 --foo", Some(6)),
+            ("flex -mt-4", None),
+        ] {
+            assert_eq!(
+                fast_skip_to_css_variable(&Cursor::new(input.as_bytes())),
+                expected,
+                "input: {input:?}"
+            );
+        }
+    }
+}
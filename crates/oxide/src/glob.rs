@@ -3,10 +3,11 @@ use fxhash::{FxHashMap, FxHashSet};
 use std::path::{Path, PathBuf};
 use tracing::event;
 
-use crate::GlobEntry;
+use crate::{Diagnostic, GlobEntry};
 
-pub fn hoist_static_glob_parts(entries: &Vec<GlobEntry>) -> Vec<GlobEntry> {
+pub fn hoist_static_glob_parts(entries: &Vec<GlobEntry>) -> (Vec<GlobEntry>, Vec<Diagnostic>) {
     let mut result = vec![];
+    let mut diagnostics = vec![];
 
     for entry in entries {
         let (static_part, dynamic_part) = split_pattern(&entry.pattern);
@@ -21,6 +22,10 @@ pub fn hoist_static_glob_parts(entries: &Vec<GlobEntry>) -> Vec<GlobEntry> {
             Ok(base) => base,
             Err(err) => {
                 event!(tracing::Level::ERROR, "Failed to resolve glob: {:?}", err);
+                diagnostics.push(Diagnostic::MissingSourceBase {
+                    base: entry.base.clone(),
+                    pattern: entry.pattern.clone(),
+                });
                 // If we can't resolve the new base on disk, let's just skip this entry.
                 continue;
             }
@@ -56,7 +61,7 @@ pub fn hoist_static_glob_parts(entries: &Vec<GlobEntry>) -> Vec<GlobEntry> {
         });
     }
 
-    result
+    (result, diagnostics)
 }
 
 /// This function attempts to optimize the glob patterns to improve performance. The problem is
@@ -82,46 +87,73 @@ pub fn hoist_static_glob_parts(entries: &Vec<GlobEntry>) -> Vec<GlobEntry> {
 /// tailwind --pwd ./project/pages --content "**/*.js"
 /// tailwind --pwd ./project/components --content "**/*.js"
 /// ```
-pub fn optimize_patterns(entries: &Vec<GlobEntry>) -> Vec<GlobEntry> {
-    let entries = hoist_static_glob_parts(entries);
+///
+/// Negated patterns (e.g. `!src/legacy/**`) are not hoisted, they stay relative to their base. The
+/// negation symbol is moved in front of the combined pattern: `!{legacy/**,**/*.stories.tsx}`.
+pub fn optimize_patterns(entries: &[GlobEntry]) -> Vec<GlobEntry> {
+    let (negated, entries): (Vec<_>, Vec<_>) =
+        entries.iter().cloned().partition(GlobEntry::is_negated);
+
+    let (entries, _) = hoist_static_glob_parts(&entries);
 
     // Track all base paths and their patterns. Later we will turn them back into `GlobalEntry`s.
-    let mut pattern_map: FxHashMap<String, FxHashSet<String>> = FxHashMap::default();
+    let mut pattern_map: FxHashMap<(String, bool), FxHashSet<String>> = FxHashMap::default();
 
     for glob_entry in entries {
-        let entry = pattern_map.entry(glob_entry.base).or_default();
+        let entry = pattern_map.entry((glob_entry.base, false)).or_default();
         entry.insert(glob_entry.pattern.clone());
     }
 
+    for glob_entry in negated {
+        let base = match dunce::canonicalize(&glob_entry.base) {
+            Ok(base) => base.to_string_lossy().to_string(),
+            Err(_) => glob_entry.base,
+        };
+
+        let entry = pattern_map.entry((base, true)).or_default();
+        entry.insert(glob_entry.pattern[1..].to_owned());
+    }
+
     let mut glob_entries = pattern_map
         .into_iter()
-        .map(|(base, patterns)| {
+        .map(|((base, negated), patterns)| {
             let size = patterns.len();
 
             let mut patterns = patterns.into_iter();
 
+            let pattern = match size {
+                // SAFETY: we can unwrap here because we know that the size is 1.
+                1 => patterns.next().unwrap(),
+                _ => {
+                    let mut patterns = patterns.collect::<Vec<_>>();
+
+                    // Sort the patterns to ensure stable results.
+                    patterns.sort();
+
+                    // TODO: Right now this will generate something like `{**/*.html,**/*.js}`,
+                    // but maybe we want to generate this instead:`**/*.{html,js}`.
+                    format!("{{{}}}", patterns.join(","))
+                }
+            };
+
             GlobEntry {
                 base,
-                pattern: match size {
-                    // SAFETY: we can unwrap here because we know that the size is 1.
-                    1 => patterns.next().unwrap(),
-                    _ => {
-                        let mut patterns = patterns.collect::<Vec<_>>();
-
-                        // Sort the patterns to ensure stable results.
-                        patterns.sort();
-
-                        // TODO: Right now this will generate something like `{**/*.html,**/*.js}`,
-                        // but maybe we want to generate this instead:`**/*.{html,js}`.
-                        format!("{{{}}}", patterns.join(","))
-                    }
+                pattern: if negated {
+                    format!("!{}", pattern)
+                } else {
+                    pattern
                 },
             }
         })
         .collect::<Vec<GlobEntry>>();
 
-    // Sort the entries by base path to ensure we have stable results.
-    glob_entries.sort_by(|a, z| a.base.cmp(&z.base));
+    // Sort the entries by base path to ensure we have stable results. Negated patterns come after
+    // the patterns they exclude from.
+    glob_entries.sort_by(|a, z| {
+        a.base
+            .cmp(&z.base)
+            .then(a.is_negated().cmp(&z.is_negated()))
+    });
 
     glob_entries
 }
@@ -176,6 +208,69 @@ pub fn path_matches_globs(path: &Path, globs: &[GlobEntry]) -> bool {
         .any(|g| glob_match(format!("{}/{}", g.base, g.pattern), path.as_bytes()))
 }
 
+/// Paths excluded by negated sources, e.g. `!src/legacy/**` or `!**/*.stories.tsx`.
+///
+/// A negated pattern that points to a directory excludes everything inside of it as well.
+#[derive(Debug, Clone, Default)]
+pub struct Exclusions {
+    /// Absolute patterns, with and without a trailing `/**`
+    patterns: Vec<(String, String)>,
+}
+
+impl Exclusions {
+    pub fn new(sources: &[GlobEntry]) -> Self {
+        let patterns = sources
+            .iter()
+            .filter(|source| source.is_negated())
+            .map(|source| {
+                let mut base = match dunce::canonicalize(&source.base) {
+                    Ok(base) => base,
+                    Err(_) => PathBuf::from(&source.base),
+                };
+
+                let mut pattern = &source.pattern[1..];
+                loop {
+                    if let Some(rest) = pattern.strip_prefix("./") {
+                        pattern = rest;
+                    } else if let Some(rest) = pattern.strip_prefix("../") {
+                        base.pop();
+                        pattern = rest;
+                    } else {
+                        break;
+                    }
+                }
+
+                let pattern = pattern.trim_end_matches('/');
+                let pattern = if pattern.starts_with('/') {
+                    pattern.to_owned()
+                } else {
+                    format!("{}/{}", base.to_string_lossy().replace('\\', "/"), pattern)
+                };
+
+                (format!("{}/**", pattern), pattern)
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        let path = path.to_string_lossy().replace('\\', "/");
+
+        self.patterns
+            .iter()
+            .any(|(nested, exact)| glob_match(exact, &path) || glob_match(nested, &path))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::optimize_patterns;
@@ -541,9 +636,7 @@ mod tests {
 
         let expected = vec![GlobEntry {
             base: "/projects".to_string(),
-            // TODO: This is wrong, because `!` should be in front. But right now we don't support
-            // `@source "!../foo/bar";` anyway.
-            pattern: "{!bar/*.html,!foo/*.html}".to_string(),
+            pattern: "!{bar/*.html,foo/*.html}".to_string(),
         }];
 
         assert_eq!(actual, expected,);
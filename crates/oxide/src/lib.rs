@@ -1,9 +1,15 @@
-use crate::glob::hoist_static_glob_parts;
+use crate::glob::{hoist_static_glob_parts, Exclusions};
 use crate::scanner::allowed_paths::resolve_paths;
+use crate::scanner::cache::{CachedFile, ScannerCache};
+use crate::scanner::candidate_index::CandidateIndex;
 use crate::scanner::detect_sources::DetectSources;
+use crate::scanner::file_stamp::FileStamp;
+use crate::scanner::multi_line::extract_multi_line;
+use crate::scanner::watcher::Watcher;
 use bexpand::Expression;
 use bstr::ByteSlice;
-use extractor::{Extracted, Extractor};
+use extractor::dynamic_class::{find_dynamic_classes, is_dynamic_class_extension};
+use extractor::{Extracted, Extractor, ExtractorOptions};
 use fast_glob::glob_match;
 use fxhash::{FxHashMap, FxHashSet};
 use glob::optimize_patterns;
@@ -11,10 +17,13 @@ use paths::Path;
 use rayon::prelude::*;
 use scanner::allowed_paths::read_dir;
 use std::borrow::Cow;
+use std::fmt;
 use std::fs;
+use std::io;
 use std::path::PathBuf;
 use std::sync;
-use std::time::SystemTime;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
 use tracing::event;
 
 pub mod cursor;
@@ -22,9 +31,26 @@ pub mod extractor;
 pub mod fast_skip;
 pub mod glob;
 pub mod paths;
+pub mod position;
 pub mod scanner;
 pub mod throughput;
 
+pub use extractor::candidate_parser::{
+    normalize_separator, parse_candidate, parse_candidate_with_separator, Candidate, Utility,
+    Value, Variant,
+};
+pub use extractor::class_context::ClassContext;
+pub use extractor::dynamic_class::DynamicClassKind;
+pub use extractor::machine::Span;
+pub use extractor::pre_processors::{compound_extension, PreProcessor, PreProcessorRegistry};
+pub use extractor::separator::Separator;
+pub use position::{Position, PositionConverter, PositionEncoding};
+pub use scanner::file_stamp::ChangeDetection;
+pub use scanner::watcher::WatchBatch;
+
+/// Default upper bound for the amount of file contents that is kept in memory while extracting
+const DEFAULT_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
 static SHOULD_TRACE: sync::LazyLock<bool> = sync::LazyLock::new(
     || matches!(std::env::var("DEBUG"), Ok(value) if value.eq("*") || (value.contains("tailwindcss:oxide") && !value.contains("-tailwindcss:oxide"))),
 );
@@ -60,6 +86,172 @@ pub struct ScanResult {
     pub candidates: Vec<String>,
     pub files: Vec<String>,
     pub globs: Vec<GlobEntry>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Scan all sources once, without keeping any state around.
+///
+/// Sources with a relative base are resolved relative to `base`. If there are no sources, then
+/// `base` itself is scanned using automatic source detection.
+#[tracing::instrument(skip_all)]
+pub fn scan(options: ScanOptions) -> ScanResult {
+    let sources = match options.base {
+        Some(base) if options.sources.is_empty() => vec![GlobEntry {
+            base,
+            pattern: "**/*".into(),
+        }],
+        Some(base) => options
+            .sources
+            .into_iter()
+            .map(|source| {
+                if std::path::Path::new(&source.base).is_absolute() {
+                    return source;
+                }
+
+                GlobEntry {
+                    base: PathBuf::from(&base)
+                        .join(&source.base)
+                        .to_string_lossy()
+                        .into(),
+                    pattern: source.pattern,
+                }
+            })
+            .collect(),
+        None => options.sources,
+    };
+
+    let mut scanner = Scanner::new(Some(sources));
+    let candidates = scanner.scan();
+
+    ScanResult {
+        candidates,
+        files: scanner.resolved_files(),
+        diagnostics: scanner.collect_diagnostics(),
+        globs: scanner.globs,
+    }
+}
+
+/// Changes to the set of candidates since the previous call to [`Scanner::scan_changes`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanChanges {
+    /// Candidates that are new, sorted
+    pub added: Vec<String>,
+    /// Candidates that are not used anymore, sorted
+    pub removed: Vec<String>,
+    /// Files that changed or were removed, sorted
+    pub changed_files: Vec<String>,
+}
+
+/// A location of a candidate inside of a file.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CandidateLocation {
+    /// Path of the file containing the candidate
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number, counted in the position encoding of the scanner (code points by
+    /// default)
+    pub column: usize,
+}
+
+/// A class that is constructed at runtime, e.g. `bg-${color}-500` or `'text-' + size`. Only the
+/// static fragments can be extracted, so its styles are most likely missing.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DynamicClassWarning {
+    pub location: CandidateLocation,
+
+    /// The class as it is written, e.g. `bg-${color}-500`, or the fragment of a concatenation,
+    /// e.g. `text-`
+    pub source: String,
+
+    pub kind: DynamicClassKind,
+}
+
+impl fmt::Display for DynamicClassWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let CandidateLocation { file, line, column } = &self.location;
+        write!(f, "{}:{}:{}: ", file, line, column)?;
+        match self.kind {
+            DynamicClassKind::Interpolation => {
+                write!(f, "Class `{}` is interpolated", self.source)?
+            }
+            DynamicClassKind::Concatenation => {
+                write!(f, "Class fragment `{}` is concatenated", self.source)?
+            }
+        }
+        write!(
+            f,
+            " and can't be detected, use complete class names instead"
+        )
+    }
+}
+
+/// A problem that was found while scanning. These don't stop the scan, but they usually point to a
+/// misconfigured source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// A file was found, but it could not be read
+    UnreadableFile { path: PathBuf, message: String },
+
+    /// The base of a source (or the static part of its pattern) doesn't exist
+    MissingSourceBase { base: String, pattern: String },
+
+    /// The brace expansion in the pattern of a source is invalid
+    InvalidBraceExpansion { base: String, pattern: String },
+
+    /// The source is valid, but it didn't match any files
+    EmptySource { base: String, pattern: String },
+
+    /// A candidate of an inline source contains an invalid brace expansion
+    InvalidInlineSource { candidate: String },
+
+    /// The configured separator can't be used, the default `:` is used instead
+    InvalidSeparator { separator: String },
+
+    /// A pre-processor mapping refers to a pre-processor that doesn't exist
+    UnknownPreProcessor { pattern: String, name: String },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::UnreadableFile { path, message } => {
+                write!(f, "Failed to read file `{}`: {}", path.display(), message)
+            }
+            Diagnostic::MissingSourceBase { base, pattern } => write!(
+                f,
+                "Source `{}` in `{}` points to a path that doesn't exist",
+                pattern, base
+            ),
+            Diagnostic::InvalidBraceExpansion { base, pattern } => write!(
+                f,
+                "Source `{}` in `{}` contains an invalid brace expansion",
+                pattern, base
+            ),
+            Diagnostic::EmptySource { base, pattern } => {
+                write!(
+                    f,
+                    "Source `{}` in `{}` didn't match any files",
+                    pattern, base
+                )
+            }
+            Diagnostic::InvalidInlineSource { candidate } => write!(
+                f,
+                "Inline candidate `{}` contains an invalid brace expansion",
+                candidate
+            ),
+            Diagnostic::InvalidSeparator { separator } => write!(
+                f,
+                "Separator `{}` is invalid, only `:`, `_`, `|`, `~` and `^` can be used",
+                separator
+            ),
+            Diagnostic::UnknownPreProcessor { pattern, name } => write!(
+                f,
+                "Pre-processor `{}` for `{}` doesn't exist",
+                name, pattern
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -68,11 +260,43 @@ pub struct GlobEntry {
     pub pattern: String,
 }
 
+/// Candidates that are passed to the scanner directly, instead of being extracted from files. E.g.
+/// classes that only exist in a CMS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineSource {
+    /// Candidates separated by whitespace. Brace expansion is supported, e.g.:
+    /// `bg-{red,blue}-{100..900..100}`
+    pub candidates: String,
+
+    /// Drop the candidates from the output instead of adding them
+    pub negated: bool,
+}
+
+/// Run pre-processors on the files matching `pattern`, e.g. `ruby` on `jbuilder` files. The
+/// pattern is an extension (`html.slim`), or a glob (`app/views/**/*.html`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreProcessorMapping {
+    pub pattern: String,
+
+    /// Names of the pre-processors, they run in order on the output of the previous one
+    pub pre_processors: Vec<String>,
+}
+
+impl GlobEntry {
+    /// Negated entries (e.g. `!src/legacy/**`) exclude files instead of including them.
+    pub fn is_negated(&self) -> bool {
+        self.pattern.starts_with('!')
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Scanner {
     /// Glob sources
     sources: Option<Vec<GlobEntry>>,
 
+    /// Paths excluded by negated sources, these are never read
+    exclusions: Exclusions,
+
     /// Scanner is ready to scan. We delay the file system traversal for detecting all files until
     /// we actually need them.
     ready: bool,
@@ -83,51 +307,366 @@ pub struct Scanner {
     /// All directories, sub-directories, etc… we saw during source detection
     dirs: Vec<PathBuf>,
 
+    /// Files passed to `scan_content`, they don't have to be part of the sources but are forgotten
+    /// once they are removed as well
+    content_files: FxHashSet<PathBuf>,
+
     /// All generated globs
     globs: Vec<GlobEntry>,
 
-    /// Track file modification times
-    mtimes: FxHashMap<PathBuf, SystemTime>,
+    /// How to decide whether files and directories changed
+    change_detection: ChangeDetection,
+
+    /// The unit columns of candidate locations and dynamic class warnings are counted in
+    position_encoding: PositionEncoding,
+
+    /// Track stamps of files and directories to detect changes
+    stamps: FxHashMap<PathBuf, FileStamp>,
+
+    /// Track unique set of candidates, and which files they came from
+    candidates: CandidateIndex,
+
+    /// Problems found while resolving the sources
+    source_diagnostics: Vec<Diagnostic>,
+
+    /// Problems found while reading files, these are cleared once the file can be read again
+    file_diagnostics: FxHashMap<PathBuf, Diagnostic>,
+
+    /// Problems found in the options, e.g. while expanding inline sources
+    option_diagnostics: Vec<Diagnostic>,
 
-    /// Track unique set of candidates
-    candidates: FxHashSet<String>,
+    /// Native file system watcher, only present while watching
+    watcher: Option<sync::Arc<Watcher>>,
+
+    /// Upper bound in bytes for the file contents kept in memory at the same time
+    memory_limit: Option<usize>,
+
+    /// Dedicated thread pool for all parallel work, uses rayon's global pool if not set
+    thread_pool: Option<sync::Arc<rayon::ThreadPool>>,
+
+    /// Options passed to the extractor for every file and content blob
+    extractor_options: ExtractorOptions,
+
+    /// Only extract candidates inside of class contexts (strict mode)
+    class_context: Option<ClassContext>,
+
+    /// Decides which pre-processors run on each file
+    pre_processors: PreProcessorRegistry,
+
+    /// The configured pre-processor mappings, to tell whether a cache can be used
+    pre_processor_mappings: Vec<PreProcessorMapping>,
 }
 
 impl Scanner {
     pub fn new(sources: Option<Vec<GlobEntry>>) -> Self {
         Self {
+            exclusions: sources.as_deref().map(Exclusions::new).unwrap_or_default(),
             sources,
             ..Default::default()
         }
     }
 
+    /// Use a different strategy to detect changed files and directories.
+    pub fn with_change_detection(mut self, change_detection: ChangeDetection) -> Self {
+        self.change_detection = change_detection;
+        self
+    }
+
+    /// Count the columns of candidate locations and dynamic class warnings in a different unit,
+    /// e.g. UTF-16 code units for JavaScript. Columns are counted in code points by default.
+    pub fn with_position_encoding(mut self, position_encoding: PositionEncoding) -> Self {
+        self.position_encoding = position_encoding;
+        self
+    }
+
+    /// Add candidates directly, or exclude candidates from the output regardless of where they
+    /// are found.
+    pub fn with_inline_sources(mut self, sources: Vec<InlineSource>) -> Self {
+        for source in sources {
+            let mut candidates = vec![];
+
+            for candidate in source.candidates.split_ascii_whitespace() {
+                match expand_inline_candidate(candidate) {
+                    Some(expanded) => candidates.extend(expanded),
+                    None => self
+                        .option_diagnostics
+                        .push(Diagnostic::InvalidInlineSource {
+                            candidate: candidate.to_owned(),
+                        }),
+                }
+            }
+
+            if source.negated {
+                self.candidates.exclude(candidates);
+            } else {
+                self.candidates.add_content(candidates);
+            }
+        }
+
+        self
+    }
+
+    /// Limit how much file content is kept in memory at the same time while extracting
+    /// candidates. Files are read, pre-processed and extracted in batches that stay below this
+    /// limit, and their contents are released before the next batch starts. Defaults to 256 MiB.
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Run all parallel work on a dedicated thread pool with the given amount of threads, instead
+    /// of rayon's global pool. A single thread disables parallelism, `0` uses one thread per CPU.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|idx| format!("tailwindcss-scanner-{idx}"))
+            .build()
+        {
+            Ok(pool) => self.thread_pool = Some(sync::Arc::new(pool)),
+            Err(err) => {
+                event!(
+                    tracing::Level::ERROR,
+                    "Failed to create thread pool, using the global pool instead: {}",
+                    err
+                );
+            }
+        }
+
+        self
+    }
+
+    /// Only extract candidates that use `prefix` as their first variant, e.g. `tw` for
+    /// `tw:flex`. Everything else in the files is ignored, CSS variables are still extracted.
+    /// Inline sources and [`Scanner::get_candidates_with_positions`] are not affected.
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        let prefix = prefix.strip_suffix(':').unwrap_or(&prefix);
+        self.extractor_options.prefix = (!prefix.is_empty()).then(|| prefix.to_owned());
+        self
+    }
+
+    /// Use a custom separator between variants, e.g. `__` for `hover__flex`. Extracted candidates
+    /// are normalized to the `:` form, e.g. `hover:flex`. An invalid separator is reported as a
+    /// diagnostic, and the default `:` is used instead.
+    pub fn with_separator(mut self, separator: String) -> Self {
+        match Separator::new(&separator) {
+            Some(separator) => self.extractor_options.separator = separator,
+            None => self
+                .option_diagnostics
+                .push(Diagnostic::InvalidSeparator { separator }),
+        }
+
+        self
+    }
+
+    /// Only extract candidates inside of class contexts, e.g. `class="…"` attributes or `clsx(…)`
+    /// calls, instead of everything that looks like a candidate. CSS variables are still
+    /// extracted everywhere.
+    ///
+    /// Class syntax that pre-processors understand outside of attributes, e.g. Svelte's
+    /// `class:underline` directives or `.card.p-4` in Haml and Slim, isn't a class context, so
+    /// these classes aren't extracted in strict mode.
+    pub fn with_class_context(mut self, class_context: ClassContext) -> Self {
+        self.class_context = Some(class_context);
+        self
+    }
+
+    /// Run pre-processors on files matching the patterns of `mappings`, in addition to the
+    /// built-in ones. Globs take precedence over extensions, and more specific extensions over
+    /// less specific ones, e.g. `html.erb` over `erb`. Otherwise later mappings take precedence
+    /// over earlier ones and over the built-in mappings. Unknown pre-processors are reported as
+    /// diagnostics and skipped.
+    pub fn with_pre_processors(mut self, mappings: Vec<PreProcessorMapping>) -> Self {
+        for mapping in &mappings {
+            let mut chain = vec![];
+
+            for name in &mapping.pre_processors {
+                match PreProcessorRegistry::builtin(name) {
+                    Some(pre_processor) => chain.push(pre_processor),
+                    None => self
+                        .option_diagnostics
+                        .push(Diagnostic::UnknownPreProcessor {
+                            pattern: mapping.pattern.clone(),
+                            name: name.clone(),
+                        }),
+                }
+            }
+
+            self.pre_processors.register(&mapping.pattern, chain);
+        }
+
+        self.pre_processor_mappings.extend(mappings);
+        self
+    }
+
     pub fn scan(&mut self) -> Vec<String> {
-        init_tracing();
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            init_tracing();
 
-        self.prepare();
-        self.compute_candidates();
+            self.prepare();
+            self.compute_candidates();
 
-        let mut candidates: Vec<String> = self.candidates.clone().into_par_iter().collect();
-        candidates.par_sort_unstable();
+            let mut candidates: Vec<String> = self.candidates.candidates().cloned().collect();
+            candidates.par_sort_unstable();
 
-        candidates
+            candidates
+        })
     }
 
+    /// Scan for changes since the previous call. The first call reports all candidates and files
+    /// as changed.
+    ///
+    /// Changes made by other methods in between (e.g. `scan_content`) are included as well.
     #[tracing::instrument(skip_all)]
-    pub fn scan_content(&mut self, changed_content: Vec<ChangedContent>) -> Vec<String> {
-        self.prepare();
-        let candidates = parse_all_blobs(read_all_files(changed_content));
+    pub fn scan_changes(&mut self) -> ScanChanges {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            init_tracing();
 
-        let mut new_candidates = vec![];
-        for candidate in candidates {
-            if self.candidates.contains(&candidate) {
-                continue;
+            self.prepare();
+            self.compute_candidates();
+
+            self.take_scan_changes()
+        })
+    }
+
+    /// Watch all directories of the scanner for changes.
+    ///
+    /// Changes are delivered as debounced batches on the returned channel. Pass them to
+    /// `scan_watch_batch` to update the candidates. Directories that are created later on are
+    /// watched automatically. Calling `unwatch` or dropping the scanner stops the watcher.
+    pub fn watch(&mut self, debounce: Duration) -> io::Result<Receiver<WatchBatch>> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            init_tracing();
+
+            self.prepare();
+
+            let (watcher, receiver) = Watcher::new(debounce)?;
+
+            // Files from glob sources don't necessarily live in a directory we know about
+            let dirs = self
+                .dirs
+                .iter()
+                .map(|dir| dir.as_path())
+                .chain(self.files.iter().filter_map(|file| file.parent()))
+                .collect::<FxHashSet<_>>();
+
+            for dir in dirs {
+                match watcher.watch(dir) {
+                    Ok(()) => {}
+
+                    // The directory was removed in the meantime
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+
+                    Err(err) => return Err(err),
+                }
             }
-            self.candidates.insert(candidate.clone());
-            new_candidates.push(candidate);
+
+            self.watcher = Some(sync::Arc::new(watcher));
+
+            Ok(receiver)
+        })
+    }
+
+    /// Stop watching for changes.
+    pub fn unwatch(&mut self) {
+        self.watcher = None;
+    }
+
+    /// Update the candidates for a batch of changes reported by the watcher. Only the changed
+    /// files are extracted again.
+    ///
+    /// Returns the changes since the previous call to `scan_changes` or `scan_watch_batch`. Both
+    /// drain the same changes, so use only one of them to keep track of the candidates.
+    #[tracing::instrument(skip_all)]
+    pub fn scan_watch_batch(&mut self, batch: &WatchBatch) -> ScanChanges {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            init_tracing();
+
+            if batch.rescan || !self.ready {
+                self.prepare();
+                self.compute_candidates();
+
+                let dirs = self.dirs.clone();
+                let new_files = self.watch_dirs(dirs);
+                self.update_files(&new_files);
+            } else {
+                self.apply_watched_paths(&batch.paths);
+            }
+
+            self.take_scan_changes()
+        })
+    }
+
+    fn take_scan_changes(&mut self) -> ScanChanges {
+        let changes = self.candidates.take_changes();
+
+        let mut added: Vec<_> = changes.added.into_iter().collect();
+        added.par_sort_unstable();
+
+        let mut removed: Vec<_> = changes.removed.into_iter().collect();
+        removed.par_sort_unstable();
+
+        let mut changed_files: Vec<_> = changes
+            .files
+            .into_iter()
+            .map(|path| Path::from(path).to_string())
+            .collect();
+        changed_files.par_sort_unstable();
+
+        ScanChanges {
+            added,
+            removed,
+            changed_files,
         }
+    }
 
-        new_candidates
+    #[tracing::instrument(skip_all)]
+    pub fn scan_content(&mut self, changed_content: Vec<ChangedContent>) -> Vec<String> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+
+            let mut changed_files = vec![];
+            let mut changed_blobs = vec![];
+
+            for changed_content in changed_content {
+                match changed_content {
+                    // Excluded files are never read
+                    ChangedContent::File(path, _) if self.exclusions.is_excluded(&path) => {}
+                    ChangedContent::File(path, extension) => changed_files.push((path, extension)),
+                    ChangedContent::Content(content, extension) => {
+                        let size = content.len() as u64;
+                        changed_blobs.push((ChangedContent::Content(content, extension), size));
+                    }
+                }
+            }
+
+            self.content_files
+                .extend(changed_files.iter().map(|(path, _)| path.clone()));
+
+            let sizes = changed_files
+                .par_iter()
+                .map(|(path, _)| fs::metadata(path).map(|m| m.len()).unwrap_or_default())
+                .collect::<Vec<_>>();
+
+            let mut new_candidates =
+                self.extract_files(changed_files.into_iter().zip(sizes).collect());
+
+            for batch in split_into_batches(changed_blobs, self.memory_limit()) {
+                let candidates = parse_all_blobs(
+                    read_all_files(batch, self.class_context.as_ref(), &self.pre_processors),
+                    &self.extractor_options,
+                );
+                new_candidates.extend(self.candidates.add_content(candidates));
+            }
+
+            new_candidates.par_sort_unstable();
+
+            new_candidates
+        })
     }
 
     #[tracing::instrument(skip_all)]
@@ -135,9 +674,24 @@ impl Scanner {
         &mut self,
         changed_content: ChangedContent,
     ) -> Vec<(String, usize)> {
-        self.prepare();
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
 
-        let content = read_changed_content(changed_content).unwrap_or_default();
+            let (content, path, extension) =
+                read_original_content(changed_content).unwrap_or_default();
+
+            self.candidates_with_positions(&content, path.as_deref(), &extension)
+        })
+    }
+
+    fn candidates_with_positions(
+        &self,
+        content: &[u8],
+        path: Option<&std::path::Path>,
+        extension: &str,
+    ) -> Vec<(String, usize)> {
+        let content = prepare_content(content, path, extension, None, &self.pre_processors);
         let original_content = &content;
 
         // Workaround for legacy upgrades:
@@ -173,10 +727,350 @@ impl Scanner {
             .collect()
     }
 
+    /// Same as [`Scanner::get_candidates_with_positions`], but with the start and end of every
+    /// candidate as a 0-based line and column in the given encoding, e.g. for language servers
+    /// and codemods. Candidates are sorted by position.
+    pub fn get_candidates_with_ranges(
+        &mut self,
+        changed_content: ChangedContent,
+        encoding: PositionEncoding,
+    ) -> Vec<(String, position::Range)> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+
+            // Positions are computed on the original content, which is what editors show
+            let (content, path, extension) =
+                read_original_content(changed_content).unwrap_or_default();
+
+            let mut candidates =
+                self.candidates_with_positions(&content, path.as_deref(), &extension);
+            candidates.sort_unstable_by_key(|(_, offset)| *offset);
+
+            let mut converter = PositionConverter::new(&content, encoding);
+
+            candidates
+                .into_iter()
+                .map(|(candidate, offset)| {
+                    let range = converter.range(offset, candidate.len());
+                    (candidate, range)
+                })
+                .collect()
+        })
+    }
+
+    /// Find every location of every candidate across all scanned files.
+    ///
+    /// The result is sorted by candidate, and the locations of each candidate are sorted by file,
+    /// line and column.
+    #[tracing::instrument(skip_all)]
+    pub fn get_candidate_locations(&mut self) -> Vec<(String, Vec<CandidateLocation>)> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+            self.compute_candidates();
+
+            // Files without candidates don't have any locations either
+            let files: Vec<_> = self
+                .candidates
+                .files()
+                .filter(|path| {
+                    self.candidates
+                        .file_candidates(path)
+                        .is_some_and(|candidates| !candidates.is_empty())
+                })
+                .map(|path| {
+                    let size = self.stamps.get(path).map_or(0, |stamp| stamp.size);
+                    (path.clone(), size)
+                })
+                .collect();
+
+            let index = &self.candidates;
+            let options = &self.extractor_options;
+            let class_context = self.class_context.as_ref();
+            let pre_processors = &self.pre_processors;
+            let encoding = self.position_encoding;
+
+            let mut locations: FxHashMap<String, Vec<CandidateLocation>> = FxHashMap::default();
+
+            for batch in split_into_batches(files, self.memory_limit()) {
+                let batch_locations = batch
+                    .into_par_iter()
+                    .filter_map(|path| {
+                        // Positions are computed on the original content, pre-processors and
+                        // class contexts can replace characters with spaces
+                        let content = fs::read(&path).ok()?;
+                        let blob = prepare_content(
+                            &content,
+                            Some(&path),
+                            &compound_extension(&path),
+                            class_context,
+                            pre_processors,
+                        );
+                        let file = Path::from(path).to_string();
+
+                        let mut candidates = extract_locations(&blob, options);
+                        candidates.sort_unstable_by_key(|(_, offset)| *offset);
+
+                        let mut converter = PositionConverter::new(&content, encoding);
+                        let mut result: FxHashMap<String, Vec<CandidateLocation>> =
+                            FxHashMap::default();
+                        for (candidate, offset) in candidates {
+                            // SAFETY: When we parsed the candidates, we already guaranteed that
+                            // the byte slices are valid, therefore we don't have to re-check here
+                            // when we want to convert it back to a string.
+                            let candidate =
+                                unsafe { String::from_utf8_unchecked(candidate.into_owned()) };
+
+                            if index.is_excluded(&candidate) {
+                                continue;
+                            }
+
+                            let position = converter.get(offset);
+                            result
+                                .entry(candidate)
+                                .or_default()
+                                .push(CandidateLocation {
+                                    file: file.clone(),
+                                    line: position.line + 1,
+                                    column: position.column + 1,
+                                });
+                        }
+
+                        Some(result)
+                    })
+                    .reduce(FxHashMap::default, |mut a, b| {
+                        for (candidate, locations) in b {
+                            a.entry(candidate).or_default().extend(locations);
+                        }
+                        a
+                    });
+
+                for (candidate, batch_locations) in batch_locations {
+                    locations
+                        .entry(candidate)
+                        .or_default()
+                        .extend(batch_locations);
+                }
+            }
+
+            let mut result: Vec<_> = locations
+                .into_iter()
+                .map(|(candidate, mut locations)| {
+                    locations.sort_unstable();
+                    locations.dedup();
+                    (candidate, locations)
+                })
+                .collect();
+
+            result.par_sort_unstable_by(|a, z| a.0.cmp(&z.0));
+
+            result
+        })
+    }
+
+    /// Find classes that are constructed at runtime across all scanned files, e.g.
+    /// `bg-${color}-500` or `'text-' + size`. This is a heuristic lint, it only runs when called
+    /// and doesn't affect the extracted candidates. Only JavaScript, TypeScript, PHP, Ruby and
+    /// template files are checked.
+    ///
+    /// Warnings are sorted by file, line and column.
+    #[tracing::instrument(skip_all)]
+    pub fn get_dynamic_class_warnings(&mut self) -> Vec<DynamicClassWarning> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+            self.compute_candidates();
+
+            let files: Vec<_> = self
+                .candidates
+                .files()
+                .filter(|path| is_dynamic_class_extension(&compound_extension(path)))
+                .cloned()
+                .collect();
+
+            let mut result: Vec<_> = files
+                .into_par_iter()
+                // The original content, pre-processors could remove the interpolations. Files that
+                // can't be read anymore are reported by the next scan.
+                .filter_map(|path| Some((fs::read(&path).ok()?, path)))
+                .flat_map_iter(|(content, path)| {
+                    let file = Path::from(path).to_string();
+                    let mut converter = PositionConverter::new(&content, self.position_encoding);
+
+                    find_dynamic_classes(&content)
+                        .into_iter()
+                        .map(|dynamic_class| {
+                            let span = dynamic_class.span;
+                            let position = converter.get(span.start);
+
+                            DynamicClassWarning {
+                                location: CandidateLocation {
+                                    file: file.clone(),
+                                    line: position.line + 1,
+                                    column: position.column + 1,
+                                },
+                                source: String::from_utf8_lossy(span.slice(&content)).into_owned(),
+                                kind: dynamic_class.kind,
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            result.par_sort_unstable();
+
+            result
+        })
+    }
+
+    /// Restore the state of a previous scanner from a cache file written by
+    /// [`Scanner::save_cache`]. This has to happen before anything is scanned.
+    ///
+    /// Files are only extracted again if their modification time or size changed, and only
+    /// directories that changed are checked for new files.
+    ///
+    /// Returns `false` if the cache can't be used, e.g. because it was created for different
+    /// sources. In that case the scanner starts from scratch.
+    #[tracing::instrument(skip_all)]
+    pub fn load_cache(&mut self, path: &std::path::Path) -> io::Result<bool> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            if self.ready {
+                return Ok(false);
+            }
+
+            let cache = ScannerCache::decode(&fs::read(path)?)?;
+
+            if cache.sources != self.canonical_sources()
+                || cache.change_detection != self.change_detection
+                || cache.prefix != self.extractor_options.prefix
+                || cache.separator != self.extractor_options.separator
+                || cache.class_context != self.class_context
+                || cache.pre_processors != self.pre_processor_mappings
+            {
+                return Ok(false);
+            }
+
+            let current_stamps = cache
+                .files
+                .par_iter()
+                .map(|file| FileStamp::read(&file.path, self.change_detection).ok())
+                .collect::<Vec<_>>();
+
+            for (file, current_stamp) in cache.files.into_iter().zip(current_stamps) {
+                // Only trust the candidates if the file didn't change since it was extracted,
+                // otherwise the next scan will extract it again.
+                if file.stamp.is_some() && file.stamp == current_stamp {
+                    self.stamps
+                        .insert(file.path.clone(), current_stamp.unwrap());
+                    self.candidates
+                        .set_file(file.path.clone(), file.candidates.into_iter().collect());
+                }
+
+                self.files.push(file.path);
+            }
+
+            for (path, stamp) in cache.dirs {
+                if let Some(stamp) = stamp {
+                    self.stamps.insert(path.clone(), stamp);
+                }
+
+                self.dirs.push(path);
+            }
+
+            self.globs = cache.globs;
+            self.source_diagnostics = cache.diagnostics;
+            self.ready = true;
+
+            Ok(true)
+        })
+    }
+
+    /// Write the current state of the scanner to a cache file, so that it can be restored with
+    /// [`Scanner::load_cache`] in another process.
+    #[tracing::instrument(skip_all)]
+    pub fn save_cache(&mut self, path: &std::path::Path) -> io::Result<()> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+
+            let files = self
+                .files
+                .iter()
+                .map(|path| {
+                    let mut candidates: Vec<_> = self
+                        .candidates
+                        .file_candidates(path)
+                        .map(|candidates| candidates.iter().cloned().collect())
+                        .unwrap_or_default();
+                    candidates.sort_unstable();
+
+                    CachedFile {
+                        path: path.clone(),
+                        stamp: self.stamps.get(path).copied(),
+                        candidates,
+                    }
+                })
+                .collect();
+
+            let dirs = self
+                .dirs
+                .iter()
+                .map(|path| (path.clone(), self.stamps.get(path).copied()))
+                .collect();
+
+            let cache = ScannerCache {
+                sources: self.canonical_sources(),
+                change_detection: self.change_detection,
+                prefix: self.extractor_options.prefix.clone(),
+                separator: self.extractor_options.separator,
+                class_context: self.class_context.clone(),
+                pre_processors: self.pre_processor_mappings.clone(),
+                files,
+                dirs,
+                globs: self.globs.clone(),
+                diagnostics: self.source_diagnostics.clone(),
+            };
+
+            write_atomic(path, &cache.encode()?)
+        })
+    }
+
+    /// The sources with absolute, canonical bases. Relative bases resolve against the working
+    /// directory, so a cache for `./src` in one project can't be used for `./src` in another.
+    fn canonical_sources(&self) -> Option<Vec<GlobEntry>> {
+        self.sources.as_ref().map(|sources| {
+            sources
+                .iter()
+                .map(|source| {
+                    let base = dunce::canonicalize(&source.base)
+                        .or_else(|_| std::path::absolute(&source.base))
+                        .map_or_else(
+                            |_| source.base.clone(),
+                            |base| base.to_string_lossy().into_owned(),
+                        );
+
+                    GlobEntry {
+                        base,
+                        pattern: source.pattern.clone(),
+                    }
+                })
+                .collect()
+        })
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn get_files(&mut self) -> Vec<String> {
-        self.prepare();
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+
+            self.resolved_files()
+        })
+    }
 
+    fn resolved_files(&self) -> Vec<String> {
         self.files
             .par_iter()
             .filter_map(|x| Path::from(x.clone()).canonicalize().ok())
@@ -184,34 +1078,118 @@ impl Scanner {
             .collect()
     }
 
+    /// All problems found so far. Problems with sources are found as soon as the sources are
+    /// resolved, problems with files are only found once the files are scanned.
+    #[tracing::instrument(skip_all)]
+    pub fn get_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
+
+            self.collect_diagnostics()
+        })
+    }
+
+    fn collect_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut file_diagnostics: Vec<_> = self.file_diagnostics.iter().collect();
+        file_diagnostics.sort_unstable_by(|a, z| a.0.cmp(z.0));
+
+        self.source_diagnostics
+            .iter()
+            .chain(self.option_diagnostics.iter())
+            .chain(
+                file_diagnostics
+                    .into_iter()
+                    .map(|(_, diagnostic)| diagnostic),
+            )
+            .cloned()
+            .collect()
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn get_globs(&mut self) -> Vec<GlobEntry> {
-        self.prepare();
+        let pool = self.thread_pool.clone();
+        install(pool.as_deref(), || {
+            self.prepare();
 
-        self.globs.clone()
+            self.globs.clone()
+        })
     }
 
     #[tracing::instrument(skip_all)]
     fn compute_candidates(&mut self) {
-        let mut changed_content = vec![];
+        let files = self.files.clone();
+        self.update_files(&files);
+        self.remove_deleted_content_files();
+    }
 
-        let current_mtimes = self
-            .files
+    /// Forget about files from `scan_content` that don't exist anymore
+    fn remove_deleted_content_files(&mut self) {
+        let removed_files = self
+            .content_files
             .par_iter()
-            .map(|path| {
-                fs::metadata(path)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::now())
+            .filter(|path| {
+                fs::metadata(path).is_err_and(|err| err.kind() == io::ErrorKind::NotFound)
             })
+            .cloned()
             .collect::<Vec<_>>();
 
-        for (idx, path) in self.files.iter().enumerate() {
-            let current_time = current_mtimes[idx];
-            let previous_time = self.mtimes.insert(path.clone(), current_time);
+        for path in &removed_files {
+            self.content_files.remove(path);
+            self.candidates.remove_file(path);
+            self.file_diagnostics.remove(path);
+        }
+    }
+
+    /// Extract the given files again if they changed, and forget about them if they were removed.
+    fn update_files(&mut self, files: &[PathBuf]) {
+        let mut changed_files = vec![];
+        let mut removed_files = FxHashSet::default();
 
-            let should_scan_file = match previous_time {
-                // Time has changed, so we need to re-scan the file
-                Some(prev) if prev != current_time => true,
+        let stamps = &self.stamps;
+        let options = &self.extractor_options;
+        let class_context = self.class_context.as_ref();
+        let pre_processors = &self.pre_processors;
+
+        let mut current_stamps = files
+            .par_iter()
+            .map(|path| match self.change_detection {
+                ChangeDetection::Mtime => (FileStamp::read(path, self.change_detection), None),
+
+                // The file has to be read to hash it anyway, so changed files are extracted from
+                // the same buffer right away.
+                ChangeDetection::ContentHash => read_and_extract_changed_file(
+                    path,
+                    stamps.get(path),
+                    options,
+                    class_context,
+                    pre_processors,
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        for (idx, path) in files.iter().enumerate() {
+            let (current_stamp, candidates) = &mut current_stamps[idx];
+
+            let current_stamp = match current_stamp {
+                Ok(stamp) => *stamp,
+
+                // The file doesn't exist anymore, so we have to forget about it
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    removed_files.insert(path.clone());
+                    continue;
+                }
+
+                // E.g. a permission problem, we keep what we know about the file until it can be
+                // read again
+                Err(_) => continue,
+            };
+
+            let previous_stamp = self.stamps.insert(path.clone(), current_stamp);
+
+            let should_scan_file = match previous_stamp {
+                // The file has changed, so we need to re-scan the file
+                Some(prev) if prev != current_stamp => true,
 
                 // File was in the cache, no need to re-scan
                 Some(_) => false,
@@ -220,16 +1198,162 @@ impl Scanner {
                 None => true,
             };
 
-            if should_scan_file {
-                let extension = path.extension().unwrap_or_default().to_string_lossy();
-                changed_content.push(ChangedContent::File(path.to_path_buf(), extension))
+            if let Some(candidates) = candidates.take() {
+                self.update_file(path.clone(), Ok(candidates));
+            } else if should_scan_file {
+                let extension = compound_extension(path);
+                changed_files.push(((path.to_path_buf(), extension.into()), current_stamp.size));
+            }
+        }
+
+        if !removed_files.is_empty() {
+            self.files.retain(|path| !removed_files.contains(path));
+
+            for path in &removed_files {
+                self.stamps.remove(path);
+                self.candidates.remove_file(path);
+                self.file_diagnostics.remove(path);
             }
         }
 
-        if !changed_content.is_empty() {
-            let candidates = parse_all_blobs(read_all_files(changed_content));
-            self.candidates.par_extend(candidates);
+        self.extract_files(changed_files);
+    }
+
+    /// Extract files in batches that stay below the memory limit. The candidates of each batch
+    /// are stored before the next batch is read, so that the contents of the files can be
+    /// released. Returns the candidates that are new to the scanner.
+    fn extract_files(&mut self, files: Vec<((PathBuf, Cow<str>), u64)>) -> Vec<String> {
+        let mut new_candidates = vec![];
+
+        for batch in split_into_batches(files, self.memory_limit()) {
+            for (path, candidates) in extract_all_files(
+                batch,
+                &self.extractor_options,
+                self.class_context.as_ref(),
+                &self.pre_processors,
+            ) {
+                new_candidates.extend(self.update_file(path, candidates));
+            }
         }
+
+        new_candidates
+    }
+
+    fn memory_limit(&self) -> usize {
+        self.memory_limit.unwrap_or(DEFAULT_MEMORY_LIMIT)
+    }
+
+    /// Store the extracted candidates of a file. Returns the candidates that are new to the
+    /// scanner.
+    fn update_file(
+        &mut self,
+        path: PathBuf,
+        candidates: io::Result<FxHashSet<String>>,
+    ) -> Vec<String> {
+        match candidates {
+            Ok(candidates) => {
+                self.file_diagnostics.remove(&path);
+                self.candidates.set_file(path, candidates)
+            }
+
+            // The file can't be read (anymore), so it doesn't contribute any candidates.
+            Err(err) => {
+                self.candidates.remove_file(&path);
+
+                // A file that disappeared is not a problem, it will be dropped by the next scan.
+                if err.kind() == io::ErrorKind::NotFound {
+                    self.file_diagnostics.remove(&path);
+                } else {
+                    self.file_diagnostics.insert(
+                        path.clone(),
+                        Diagnostic::UnreadableFile {
+                            path,
+                            message: err.to_string(),
+                        },
+                    );
+                }
+
+                vec![]
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn apply_watched_paths(&mut self, paths: &[PathBuf]) {
+        let mut changed_files = vec![];
+        let mut modified_dirs = vec![];
+        let mut removed_dirs = vec![];
+
+        {
+            let files = self
+                .files
+                .iter()
+                .map(|file| file.as_path())
+                .collect::<FxHashSet<_>>();
+            let dirs = self
+                .dirs
+                .iter()
+                .map(|dir| dir.as_path())
+                .collect::<FxHashSet<_>>();
+
+            for path in paths {
+                if files.contains(path.as_path()) {
+                    changed_files.push(path.clone());
+                } else if dirs.contains(path.as_path()) {
+                    if !path.is_dir() {
+                        removed_dirs.push(path.clone());
+                    }
+                } else if let Some(parent) = path.parent().filter(|parent| dirs.contains(parent)) {
+                    // A new file or directory in a directory we know about
+                    modified_dirs.push(parent.to_path_buf());
+                }
+            }
+        }
+
+        // Everything inside of a removed directory is gone as well
+        if !removed_dirs.is_empty() {
+            let is_removed = |path: &PathBuf| removed_dirs.iter().any(|dir| path.starts_with(dir));
+
+            changed_files.extend(self.files.iter().filter(|file| is_removed(file)).cloned());
+
+            for dir in self.dirs.iter().filter(|dir| is_removed(dir)) {
+                self.stamps.remove(dir);
+            }
+            self.dirs.retain(|dir| !is_removed(dir));
+        }
+
+        modified_dirs.sort_unstable();
+        modified_dirs.dedup();
+
+        let (new_files, new_dirs) = self.add_new_entries(modified_dirs);
+        changed_files.extend(new_files);
+        changed_files.extend(self.watch_dirs(new_dirs));
+
+        self.update_files(&changed_files);
+    }
+
+    /// Start watching the given directories. Returns the files that were created inside of them
+    /// before the watcher was in place, since those don't trigger any events.
+    fn watch_dirs(&mut self, mut dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut new_files = vec![];
+
+        let Some(watcher) = self.watcher.clone() else {
+            return new_files;
+        };
+
+        while !dirs.is_empty() {
+            for dir in &dirs {
+                if let Err(err) = watcher.watch(dir) {
+                    event!(tracing::Level::ERROR, "Failed to watch {:?}: {}", dir, err);
+                }
+            }
+
+            let (files, new_dirs) = self.add_new_entries(dirs);
+            new_files.extend(files);
+            dirs = new_dirs;
+        }
+
+        new_files
     }
 
     // Ensures that all files/globs are resolved and the scanner is ready to scan
@@ -247,26 +1371,26 @@ impl Scanner {
 
     #[tracing::instrument(skip_all)]
     fn check_for_new_files(&mut self) {
-        let current_mtimes = self
+        let current_stamps = self
             .dirs
             .par_iter()
-            .map(|path| {
-                fs::metadata(path)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::now())
-            })
+            .map(|path| FileStamp::read_dir(path, self.change_detection))
             .collect::<Vec<_>>();
 
         let mut modified_dirs: Vec<PathBuf> = vec![];
 
         // Check all directories to see if they were modified
         for (idx, path) in self.dirs.iter().enumerate() {
-            let current_time = current_mtimes[idx];
-            let previous_time = self.mtimes.insert(path.clone(), current_time);
+            // The directory doesn't exist anymore, so there is nothing to scan
+            let Some(current_stamp) = current_stamps[idx] else {
+                continue;
+            };
+
+            let previous_stamp = self.stamps.insert(path.clone(), current_stamp);
 
-            let should_scan = match previous_time {
-                // Time has changed, so we need to re-scan the file
-                Some(prev) if prev != current_time => true,
+            let should_scan = match previous_stamp {
+                // The directory has changed, so we need to re-scan the directory
+                Some(prev) if prev != current_stamp => true,
 
                 // File was in the cache, no need to re-scan
                 Some(_) => false,
@@ -280,13 +1404,22 @@ impl Scanner {
             }
         }
 
+        self.add_new_entries(modified_dirs);
+    }
+
+    /// Scan the given directories for files and directories we don't know about yet. Returns the
+    /// new files and directories.
+    fn add_new_entries(&mut self, mut modified_dirs: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let mut new_files = vec![];
+        let mut new_dirs = vec![];
+
         // Scan all modified directories for their immediate files
         let mut known = FxHashSet::from_iter(self.files.iter().chain(self.dirs.iter()).cloned());
 
         while !modified_dirs.is_empty() {
             let new_entries = modified_dirs
                 .iter()
-                .flat_map(|dir| read_dir(dir, Some(1)))
+                .flat_map(|dir| read_dir(dir, Some(1), &self.exclusions))
                 .map(|entry| entry.path().to_owned())
                 .filter(|path| !known.contains(path))
                 .collect::<Vec<_>>();
@@ -296,16 +1429,20 @@ impl Scanner {
             for path in new_entries {
                 if path.is_file() {
                     known.insert(path.clone());
-                    self.files.push(path);
+                    self.files.push(path.clone());
+                    new_files.push(path);
                 } else if path.is_dir() {
                     known.insert(path.clone());
                     self.dirs.push(path.clone());
+                    new_dirs.push(path.clone());
 
                     // Recursively scan the new directory for files
                     modified_dirs.push(path);
                 }
             }
         }
+
+        (new_files, new_dirs)
     }
 
     #[tracing::instrument(skip_all)]
@@ -318,42 +1455,74 @@ impl Scanner {
             return;
         }
 
-        // Expand glob patterns and create new `GlobEntry` instances for each expanded pattern.
-        let sources = sources
+        // Negated sources only exclude files from the other sources. They are tracked as
+        // `exclusions` already, but we keep them in the globs so that watchers can skip the
+        // excluded files as well.
+        let (negated, sources): (Vec<_>, Vec<_>) =
+            sources.iter().cloned().partition(GlobEntry::is_negated);
+        self.globs.extend(negated);
+
+        // Expand glob patterns and create new `GlobEntry` instances for each expanded pattern. We
+        // keep track of the index of the original source, so we can report problems with the
+        // source the user actually wrote.
+        let expanded = sources
             .iter()
-            .flat_map(|source| {
+            .enumerate()
+            .flat_map(|(idx, source)| {
                 let expression: Result<Expression, _> = source.pattern[..].try_into();
                 let Ok(expression) = expression else {
-                    return vec![source.clone()];
+                    self.source_diagnostics
+                        .push(Diagnostic::InvalidBraceExpansion {
+                            base: source.base.clone(),
+                            pattern: source.pattern.clone(),
+                        });
+                    return vec![(idx, source.clone())];
                 };
 
-                expression
-                    .into_iter()
-                    .filter_map(Result::ok)
-                    .map(move |pattern| GlobEntry {
-                        base: source.base.clone(),
-                        pattern: pattern.into(),
-                    })
-                    .collect::<Vec<_>>()
+                let mut patterns = vec![];
+                let mut is_valid = true;
+                for pattern in expression {
+                    match pattern {
+                        Ok(pattern) => patterns.push((
+                            idx,
+                            GlobEntry {
+                                base: source.base.clone(),
+                                pattern: pattern.into(),
+                            },
+                        )),
+                        Err(_) => is_valid = false,
+                    }
+                }
+
+                if !is_valid {
+                    self.source_diagnostics
+                        .push(Diagnostic::InvalidBraceExpansion {
+                            base: source.base.clone(),
+                            pattern: source.pattern.clone(),
+                        });
+                }
+
+                patterns
             })
             .collect::<Vec<_>>();
 
         // Partition sources into sources that should be promoted to auto source detection and
         // sources that should be resolved as globs.
-        let (auto_sources, glob_sources): (Vec<_>, Vec<_>) = sources.iter().partition(|source| {
-            // If a glob ends with `/**/*`, then we just want to register the base path as a new
-            // base. Essentially converting it to use auto source detection.
-            if source.pattern.ends_with("**/*") {
-                return true;
-            }
+        let (auto_sources, glob_sources): (Vec<_>, Vec<_>) =
+            expanded.into_iter().partition(|(_, source)| {
+                // If a glob ends with `/**/*`, then we just want to register the base path as a new
+                // base. Essentially converting it to use auto source detection.
+                if source.pattern.ends_with("**/*") {
+                    return true;
+                }
 
-            // Directories should be promoted to auto source detection
-            if PathBuf::from(&source.base).join(&source.pattern).is_dir() {
-                return true;
-            }
+                // Directories should be promoted to auto source detection
+                if PathBuf::from(&source.base).join(&source.pattern).is_dir() {
+                    return true;
+                }
 
-            false
-        });
+                false
+            });
 
         fn join_paths(a: &str, b: &str) -> PathBuf {
             let mut tmp = a.to_owned();
@@ -375,9 +1544,18 @@ impl Scanner {
             PathBuf::from(&tmp)
         }
 
-        for path in auto_sources.iter().filter_map(|source| {
-            dunce::canonicalize(join_paths(&source.base, &source.pattern)).ok()
-        }) {
+        // Track the amount of files each original source resolved to
+        let mut file_counts = vec![0; sources.len()];
+
+        // Track which original sources point to a base that doesn't exist
+        let mut missing_bases = vec![false; sources.len()];
+
+        for (idx, source) in &auto_sources {
+            let Ok(path) = dunce::canonicalize(join_paths(&source.base, &source.pattern)) else {
+                missing_bases[*idx] = true;
+                continue;
+            };
+
             // Insert a glob for the base path, so we can see new files/folders in the directory itself.
             self.globs.push(GlobEntry {
                 base: path.to_string_lossy().into(),
@@ -385,101 +1563,256 @@ impl Scanner {
             });
 
             // Detect all files/folders in the directory
-            let detect_sources = DetectSources::new(path);
+            let detect_sources = DetectSources::new(path).with_exclusions(self.exclusions.clone());
 
             let (files, globs, dirs) = detect_sources.detect();
+            file_counts[*idx] += files.len();
             self.files.extend(files);
             self.globs.extend(globs);
             self.dirs.extend(dirs);
         }
 
-        // Turn `Vec<&GlobEntry>` in `Vec<GlobEntry>`
-        let glob_sources: Vec<_> = glob_sources.into_iter().cloned().collect();
-        let hoisted = hoist_static_glob_parts(&glob_sources);
-
-        for source in &hoisted {
-            // If the pattern is empty, then the base points to a specific file or folder already
-            // if it doesn't contain any dynamic parts. In that case we can use the base as the
-            // pattern.
-            //
-            // Otherwise we need to combine the base and the pattern, otherwise a pattern that
-            // looks like `*.html`, will never match a path that looks like
-            // `/my-project/project-a/index.html`, because it contains `/`.
-            //
-            // We can't prepend `**/`, because then `/my-project/project-a/nested/index.html` would
-            // match as well.
-            //
-            // Instead we combine the base and the pattern as a single glob pattern.
-            let mut full_pattern = source.base.clone().replace('\\', "/");
-
-            if !source.pattern.is_empty() {
-                full_pattern.push('/');
-                full_pattern.push_str(&source.pattern);
-            }
-
-            let base = PathBuf::from(&source.base);
-            for entry in resolve_paths(&base) {
-                let Some(file_type) = entry.file_type() else {
-                    continue;
-                };
+        for (idx, source) in &glob_sources {
+            let (hoisted, diagnostics) = hoist_static_glob_parts(&vec![source.clone()]);
 
-                if !file_type.is_file() {
-                    continue;
+            if !diagnostics.is_empty() {
+                missing_bases[*idx] = true;
+            }
+
+            for source in &hoisted {
+                // If the pattern is empty, then the base points to a specific file or folder already
+                // if it doesn't contain any dynamic parts. In that case we can use the base as the
+                // pattern.
+                //
+                // Otherwise we need to combine the base and the pattern, otherwise a pattern that
+                // looks like `*.html`, will never match a path that looks like
+                // `/my-project/project-a/index.html`, because it contains `/`.
+                //
+                // We can't prepend `**/`, because then `/my-project/project-a/nested/index.html` would
+                // match as well.
+                //
+                // Instead we combine the base and the pattern as a single glob pattern.
+                let mut full_pattern = source.base.clone().replace('\\', "/");
+
+                if !source.pattern.is_empty() {
+                    full_pattern.push('/');
+                    full_pattern.push_str(&source.pattern);
                 }
 
-                let file_path = entry.into_path();
+                let base = PathBuf::from(&source.base);
+                for entry in resolve_paths(&base, &self.exclusions) {
+                    let Some(file_type) = entry.file_type() else {
+                        continue;
+                    };
 
-                let Some(file_path_str) = file_path.to_str() else {
-                    continue;
-                };
+                    if !file_type.is_file() {
+                        continue;
+                    }
+
+                    let file_path = entry.into_path();
 
-                let file_path_str = file_path_str.replace('\\', "/");
+                    let Some(file_path_str) = file_path.to_str() else {
+                        continue;
+                    };
 
-                if glob_match(&full_pattern, &file_path_str) {
-                    self.files.push(file_path);
+                    let file_path_str = file_path_str.replace('\\', "/");
+
+                    if glob_match(&full_pattern, &file_path_str) {
+                        file_counts[*idx] += 1;
+                        self.files.push(file_path);
+                    }
                 }
             }
+
+            self.globs.extend(hoisted);
         }
 
-        self.globs.extend(hoisted);
+        for (idx, source) in sources.iter().enumerate() {
+            if missing_bases[idx] {
+                event!(
+                    tracing::Level::ERROR,
+                    "Failed to resolve source: {:?}",
+                    source
+                );
+                self.source_diagnostics.push(Diagnostic::MissingSourceBase {
+                    base: source.base.clone(),
+                    pattern: source.pattern.clone(),
+                });
+            } else if file_counts[idx] == 0 {
+                self.source_diagnostics.push(Diagnostic::EmptySource {
+                    base: source.base.clone(),
+                    pattern: source.pattern.clone(),
+                });
+            }
+        }
 
         // Re-optimize the globs to reduce the number of patterns we have to scan.
         self.globs = optimize_patterns(&self.globs);
     }
 }
 
-fn read_changed_content(c: ChangedContent) -> Option<Vec<u8>> {
-    let (content, extension) = match c {
+/// Expand the brace expansions of an inline candidate. Returns `None` if the brace expansion is
+/// invalid.
+fn expand_inline_candidate(candidate: &str) -> Option<Vec<String>> {
+    let expression: Expression = candidate.try_into().ok()?;
+
+    expression
+        .into_iter()
+        .map(|candidate| candidate.ok().map(Into::into))
+        .collect()
+}
+
+/// Write to a temporary file next to `path` first, so that a crash never leaves a half written
+/// file behind. The name of the temporary file is unique, so that processes writing the same
+/// file at the same time don't interfere with each other.
+fn write_atomic(path: &std::path::Path, contents: &[u8]) -> io::Result<()> {
+    static COUNTER: sync::atomic::AtomicUsize = sync::atomic::AtomicUsize::new(0);
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, sync::atomic::Ordering::Relaxed)
+    ));
+    let tmp = path.with_file_name(name);
+
+    let result = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+
+    result
+}
+
+/// Run `f` on the given thread pool, so that all parallel work inside of it uses that pool as well.
+fn install<R: Send>(pool: Option<&rayon::ThreadPool>, f: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
+/// Read and pre-process content. With a class context, everything outside of it is blanked out
+/// before the content is split into lines, so that contexts can span multiple lines.
+fn read_changed_content(
+    c: ChangedContent,
+    class_context: Option<&ClassContext>,
+    pre_processors: &PreProcessorRegistry,
+) -> io::Result<Vec<u8>> {
+    let (content, path, extension) = read_original_content(c)?;
+
+    Ok(prepare_content(
+        &content,
+        path.as_deref(),
+        &extension,
+        class_context,
+        pre_processors,
+    ))
+}
+
+/// The content as it is before pre-processing, together with its path and extension
+fn read_original_content(c: ChangedContent) -> io::Result<(Vec<u8>, Option<PathBuf>, Cow<str>)> {
+    match c {
         ChangedContent::File(file, extension) => match std::fs::read(&file) {
-            Ok(content) => (content, extension),
+            Ok(content) => Ok((content, Some(file), extension)),
             Err(e) => {
                 event!(tracing::Level::ERROR, "Failed to read file: {:?}", e);
-                return None;
+                Err(e)
             }
         },
 
-        ChangedContent::Content(contents, extension) => (contents.into_bytes(), extension),
+        ChangedContent::Content(contents, extension) => {
+            Ok((contents.into_bytes(), None, extension))
+        }
+    }
+}
+
+/// Pre-process the content, and blank everything outside of the class contexts
+fn prepare_content(
+    content: &[u8],
+    path: Option<&std::path::Path>,
+    extension: &str,
+    class_context: Option<&ClassContext>,
+    pre_processors: &PreProcessorRegistry,
+) -> Vec<u8> {
+    let mut content = pre_processors.process(content, path, extension);
+
+    if let Some(class_context) = class_context {
+        class_context.mask(&mut content);
+    }
+
+    content
+}
+
+/// Read a file in [`ChangeDetection::ContentHash`] mode, and extract its candidates from the same
+/// buffer if it changed since its `previous_stamp`. Returns the current stamp, and the candidates
+/// if the file was extracted.
+fn read_and_extract_changed_file(
+    path: &std::path::Path,
+    previous_stamp: Option<&FileStamp>,
+    options: &ExtractorOptions,
+    class_context: Option<&ClassContext>,
+    pre_processors: &PreProcessorRegistry,
+) -> (io::Result<FileStamp>, Option<FxHashSet<String>>) {
+    // The file doesn't exist (anymore) or can't be read, the regular stamp handles both cases
+    let Ok(content) = fs::read(path) else {
+        return (FileStamp::read(path, ChangeDetection::ContentHash), None);
     };
 
-    Some(pre_process_input(&content, &extension))
+    let stamp = FileStamp::from_content(&content);
+    if previous_stamp == Some(&stamp) {
+        return (Ok(stamp), None);
+    }
+
+    let blob = prepare_content(
+        &content,
+        Some(path),
+        &compound_extension(path),
+        class_context,
+        pre_processors,
+    );
+
+    (Ok(stamp), Some(extract_blob(&blob, options)))
 }
 
+/// Pre-process content with the built-in pre-processor for its extension, if any
 pub fn pre_process_input(content: &[u8], extension: &str) -> Vec<u8> {
-    use crate::extractor::pre_processors::*;
+    PreProcessorRegistry::shared().process(content, None, extension)
+}
+
+/// Split items into consecutive batches whose estimated memory usage stays below `limit`. Every
+/// item needs its size in memory twice, once for its contents and once for the pre-processed
+/// copy. Items that exceed the limit on their own end up in a batch of their own.
+fn split_into_batches<T>(items: Vec<(T, u64)>, limit: usize) -> Vec<Vec<T>> {
+    let mut batches = vec![];
+    let mut batch = vec![];
+    let mut batch_size = 0u64;
 
-    match extension {
-        "cshtml" | "razor" => Razor.process(content),
-        "haml" => Haml.process(content),
-        "pug" => Pug.process(content),
-        "rb" | "erb" => Ruby.process(content),
-        "slim" => Slim.process(content),
-        "svelte" => Svelte.process(content),
-        _ => content.to_vec(),
+    for (item, size) in items {
+        let size = size.saturating_mul(2);
+
+        if !batch.is_empty() && batch_size.saturating_add(size) > limit as u64 {
+            batches.push(std::mem::take(&mut batch));
+            batch_size = 0;
+        }
+
+        batch.push(item);
+        batch_size = batch_size.saturating_add(size);
     }
+
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    batches
 }
 
 #[tracing::instrument(skip_all)]
-fn read_all_files(changed_content: Vec<ChangedContent>) -> Vec<Vec<u8>> {
+fn read_all_files(
+    changed_content: Vec<ChangedContent>,
+    class_context: Option<&ClassContext>,
+    pre_processors: &PreProcessorRegistry,
+) -> Vec<Vec<u8>> {
     event!(
         tracing::Level::INFO,
         "Reading {:?} file(s)",
@@ -488,38 +1821,58 @@ fn read_all_files(changed_content: Vec<ChangedContent>) -> Vec<Vec<u8>> {
 
     changed_content
         .into_par_iter()
-        .filter_map(read_changed_content)
+        .filter_map(|c| read_changed_content(c, class_context, pre_processors).ok())
         .collect()
 }
 
+/// Read and extract the candidates of each file individually, so that the candidates can be
+/// attributed to the file they came from.
 #[tracing::instrument(skip_all)]
-fn parse_all_blobs(blobs: Vec<Vec<u8>>) -> Vec<String> {
-    let mut result: Vec<_> = blobs
-        .par_iter()
-        .flat_map(|blob| blob.par_split(|x| *x == b'\n'))
-        .filter_map(|blob| {
-            if blob.is_empty() {
-                return None;
-            }
-
-            let extracted = crate::extractor::Extractor::new(blob).extract();
-            if extracted.is_empty() {
-                return None;
-            }
+fn extract_all_files(
+    changed_files: Vec<(PathBuf, Cow<str>)>,
+    options: &ExtractorOptions,
+    class_context: Option<&ClassContext>,
+    pre_processors: &PreProcessorRegistry,
+) -> Vec<(PathBuf, io::Result<FxHashSet<String>>)> {
+    event!(
+        tracing::Level::INFO,
+        "Extracting {:?} file(s)",
+        changed_files.len()
+    );
 
-            Some(FxHashSet::from_iter(extracted.into_iter().map(
-                |x| match x {
-                    Extracted::Candidate(bytes) => bytes,
-                    Extracted::CssVariable(bytes) => bytes,
-                },
-            )))
+    changed_files
+        .into_par_iter()
+        .map(|(path, extension)| {
+            let candidates = read_changed_content(
+                ChangedContent::File(path.clone(), extension),
+                class_context,
+                pre_processors,
+            )
+            .map(|blob| extract_blob(&blob, options));
+
+            (path, candidates)
         })
+        .collect()
+}
+
+fn extract_blob(blob: &[u8], options: &ExtractorOptions) -> FxHashSet<String> {
+    parse_blob(blob, options)
+        .into_iter()
+        .map(|s| unsafe { String::from_utf8_unchecked(s.into_owned()) })
+        .collect()
+}
+
+#[tracing::instrument(skip_all)]
+fn parse_all_blobs(blobs: Vec<Vec<u8>>, options: &ExtractorOptions) -> Vec<String> {
+    let mut result: Vec<_> = blobs
+        .par_iter()
+        .map(|blob| parse_blob(blob, options))
         .reduce(Default::default, |mut a, b| {
             a.extend(b);
             a
         })
         .into_iter()
-        .map(|s| unsafe { String::from_utf8_unchecked(s.to_vec()) })
+        .map(|s| unsafe { String::from_utf8_unchecked(s.into_owned()) })
         .collect();
 
     // SAFETY: Unstable sort is faster and in this scenario it's also safe because we are
@@ -529,9 +1882,89 @@ fn parse_all_blobs(blobs: Vec<Vec<u8>>) -> Vec<String> {
     result
 }
 
+/// Extract all candidates of a blob together with their byte offset in the blob. Lines are
+/// extracted individually, the same way `parse_blob` does it.
+fn extract_locations<'a>(
+    blob: &'a [u8],
+    options: &ExtractorOptions,
+) -> Vec<(Cow<'a, [u8]>, usize)> {
+    let lines = blob.split(|x| *x == b'\n').collect::<Vec<_>>();
+
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .flat_map(|(idx, line)| {
+            let offset = line.as_ptr() as usize;
+            let line_offset = offset - blob.as_ptr() as usize;
+
+            let candidates = Extractor::with_options(line, options)
+                .extract()
+                .into_iter()
+                .map(move |x| {
+                    let (bytes, start) = match x {
+                        Extracted::Candidate(bytes) => (
+                            normalize_separator(bytes, options.separator),
+                            bytes.as_ptr() as usize,
+                        ),
+                        Extracted::CssVariable(bytes) => {
+                            (Cow::Borrowed(bytes), bytes.as_ptr() as usize)
+                        }
+                    };
+
+                    (bytes, start - offset)
+                })
+                .chain(
+                    extract_multi_line(&lines, idx, options)
+                        .into_iter()
+                        .map(|(bytes, start)| (Cow::Owned(bytes), start)),
+                );
+
+            candidates.map(move |(bytes, start)| (bytes, line_offset + start))
+        })
+        .collect()
+}
+
+/// Extract all candidates of a blob. Lines are extracted in parallel, candidates that continue on
+/// the next line are stitched back together.
+fn parse_blob<'a>(blob: &'a [u8], options: &ExtractorOptions) -> FxHashSet<Cow<'a, [u8]>> {
+    let lines = blob.split(|x| *x == b'\n').collect::<Vec<_>>();
+
+    lines
+        .par_iter()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            if line.is_empty() {
+                return None;
+            }
+
+            let extracted = Extractor::with_options(line, options).extract();
+            let multi_line = extract_multi_line(&lines, idx, options);
+            if extracted.is_empty() && multi_line.is_empty() {
+                return None;
+            }
+
+            Some(FxHashSet::from_iter(
+                extracted
+                    .into_iter()
+                    .map(|x| match x {
+                        Extracted::Candidate(bytes) => {
+                            normalize_separator(bytes, options.separator)
+                        }
+                        Extracted::CssVariable(bytes) => Cow::Borrowed(bytes),
+                    })
+                    .chain(multi_line.into_iter().map(|(bytes, _)| Cow::Owned(bytes))),
+            ))
+        })
+        .reduce(Default::default, |mut a, b| {
+            a.extend(b);
+            a
+        })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::Scanner;
+    use crate::{Position, PositionEncoding, Scanner};
 
     #[test]
     fn test_positions() {
@@ -572,4 +2005,28 @@ mod tests {
             assert_eq!(candidates, expected);
         }
     }
+
+    #[test]
+    fn test_ranges() {
+        let mut scanner = Scanner::new(None);
+        let input = "<p>\n  🔥 <div class=\"flex mt-[1px]\"></div>";
+
+        let ranges = scanner.get_candidates_with_ranges(
+            crate::ChangedContent::Content(input.to_string(), "html".into()),
+            PositionEncoding::Utf16,
+        );
+
+        let position = |line, column| Position { line, column };
+        assert_eq!(
+            ranges
+                .into_iter()
+                .map(|(candidate, range)| (candidate, range.start, range.end))
+                .collect::<Vec<_>>(),
+            vec![
+                ("class".to_string(), position(1, 10), position(1, 15)),
+                ("flex".to_string(), position(1, 17), position(1, 21)),
+                ("mt-[1px]".to_string(), position(1, 22), position(1, 30)),
+            ]
+        );
+    }
 }
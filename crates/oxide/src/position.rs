@@ -0,0 +1,228 @@
+//! Convert byte offsets into line and column positions, the way editors and language servers
+//! expect them.
+use bstr::ByteSlice;
+
+/// The unit columns are counted in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    /// Bytes, e.g. for Rust strings
+    Utf8,
+
+    /// UTF-16 code units, e.g. for JavaScript strings and the default of the Language Server
+    /// Protocol
+    Utf16,
+
+    /// Unicode code points, e.g. for Python strings
+    #[default]
+    CodePoint,
+}
+
+impl PositionEncoding {
+    /// The width of `input` in this encoding
+    fn width(self, input: &[u8]) -> usize {
+        match self {
+            PositionEncoding::Utf8 => input.len(),
+            PositionEncoding::Utf16 => input
+                .iter()
+                .map(|x| match x {
+                    // Continuation bytes
+                    0x80..=0xBF => 0,
+
+                    // Start of a 4 byte sequence, which needs a surrogate pair
+                    0xF0..=0xFF => 2,
+
+                    _ => 1,
+                })
+                .sum(),
+            PositionEncoding::CodePoint => {
+                input.iter().filter(|x| !matches!(x, 0x80..=0xBF)).count()
+            }
+        }
+    }
+}
+
+/// A 0-based line and column, like in the Language Server Protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The range of a candidate, the end is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Converts byte offsets of an input into [`Position`]s. Conversions are incremental, so
+/// converting offsets in ascending order only walks the input once.
+#[derive(Debug, Clone)]
+pub struct PositionConverter<'a> {
+    input: &'a [u8],
+    encoding: PositionEncoding,
+
+    /// Byte offset of the last conversion
+    offset: usize,
+
+    /// Position of the last conversion
+    position: Position,
+
+    /// Index of the last conversion, counted in the encoding from the start of the input
+    index: usize,
+}
+
+impl<'a> PositionConverter<'a> {
+    pub fn new(input: &'a [u8], encoding: PositionEncoding) -> Self {
+        Self {
+            input,
+            encoding,
+            offset: 0,
+            position: Position::default(),
+            index: 0,
+        }
+    }
+
+    /// Offsets past the end of the input are clamped to the end.
+    pub fn get(&mut self, offset: usize) -> Position {
+        let offset = offset.min(self.input.len());
+
+        if offset < self.offset {
+            self.offset = 0;
+            self.position = Position::default();
+            self.index = 0;
+        }
+
+        let slice = &self.input[self.offset..offset];
+        let width = self.encoding.width(slice);
+
+        match slice.rfind_byte(b'\n') {
+            Some(idx) => {
+                self.position.line += slice.find_iter(b"\n").count();
+                self.position.column = self.encoding.width(&slice[idx + 1..]);
+            }
+            None => self.position.column += width,
+        }
+
+        self.index += width;
+
+        self.offset = offset;
+        self.position
+    }
+
+    /// Convert a byte offset into an index counted in the encoding from the start of the input,
+    /// e.g. an index into a JavaScript string for [`PositionEncoding::Utf16`].
+    pub fn index(&mut self, offset: usize) -> usize {
+        self.get(offset);
+        self.index
+    }
+
+    /// The range of `len` bytes starting at `offset`
+    pub fn range(&mut self, offset: usize, len: usize) -> Range {
+        Range {
+            start: self.get(offset),
+            end: self.get(offset + len),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Position, PositionConverter, PositionEncoding};
+
+    fn position(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn test_position_converter() {
+        let input = "<p class=\"flex\">\n  Hello 🔥 wörld\n  <b class=\"underline\"></b>";
+
+        for (encoding, expected) in [
+            (
+                PositionEncoding::Utf8,
+                [
+                    position(0, 0),
+                    position(0, 10),
+                    position(1, 2),
+                    position(1, 13),
+                    position(2, 12),
+                ],
+            ),
+            (
+                PositionEncoding::Utf16,
+                [
+                    position(0, 0),
+                    position(0, 10),
+                    position(1, 2),
+                    position(1, 11),
+                    position(2, 12),
+                ],
+            ),
+            (
+                PositionEncoding::CodePoint,
+                [
+                    position(0, 0),
+                    position(0, 10),
+                    position(1, 2),
+                    position(1, 10),
+                    position(2, 12),
+                ],
+            ),
+        ] {
+            let mut converter = PositionConverter::new(input.as_bytes(), encoding);
+            let offsets = [
+                0,
+                input.find("flex").unwrap(),
+                input.find("Hello").unwrap(),
+                input.find("wörld").unwrap(),
+                input.find("underline").unwrap(),
+            ];
+
+            // In order, and out of order
+            for (offset, expected) in offsets.iter().zip(expected) {
+                assert_eq!(converter.get(*offset), expected, "{encoding:?}");
+            }
+            for (offset, expected) in offsets.iter().zip(expected).rev() {
+                assert_eq!(converter.get(*offset), expected, "{encoding:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_position_converter_index() {
+        let input = "Hello 🔥🥳\nworld!";
+        let mut converter = PositionConverter::new(input.as_bytes(), PositionEncoding::Utf16);
+
+        for (offset, expected) in [
+            (0, 0),
+            (6, 6),
+            // Inside of the 🔥
+            (7, 8),
+            (10, 8),
+            // Inside of the 🥳
+            (11, 10),
+            (14, 10),
+            (15, 11),
+            (21, 17),
+            // Past the end of the input
+            (100, 17),
+            // Out of order
+            (16, 12),
+        ] {
+            assert_eq!(converter.index(offset), expected, "offset: {offset}");
+        }
+    }
+
+    #[test]
+    fn test_position_converter_range() {
+        let input = "🔥 flex";
+        let mut converter = PositionConverter::new(input.as_bytes(), PositionEncoding::Utf16);
+        let range = converter.range(input.find("flex").unwrap(), 4);
+
+        assert_eq!((range.start, range.end), (position(0, 3), position(0, 7)));
+
+        // Past the end of the input
+        assert_eq!(converter.get(100), position(0, 7));
+    }
+}
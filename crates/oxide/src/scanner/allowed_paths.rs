@@ -1,3 +1,4 @@
+use crate::glob::Exclusions;
 use ignore::{DirEntry, WalkBuilder};
 use std::{path::Path, sync};
 
@@ -26,20 +27,40 @@ static IGNORED_CONTENT_DIRS: sync::LazyLock<Vec<&'static str>> =
     sync::LazyLock::new(|| vec![".git"]);
 
 #[tracing::instrument(skip_all)]
-pub fn resolve_allowed_paths(root: &Path) -> impl Iterator<Item = DirEntry> {
+pub fn resolve_allowed_paths(
+    root: &Path,
+    exclusions: &Exclusions,
+) -> impl Iterator<Item = DirEntry> {
     // Read the directory recursively with no depth limit
-    read_dir(root, None)
+    read_dir(root, None, exclusions)
 }
 
 #[tracing::instrument(skip_all)]
-pub fn resolve_paths(root: &Path) -> impl Iterator<Item = DirEntry> {
-    create_walk_builder(root).build().filter_map(Result::ok)
+pub fn resolve_paths(root: &Path, exclusions: &Exclusions) -> impl Iterator<Item = DirEntry> {
+    let mut builder = create_walk_builder(root);
+
+    // Don't descend into excluded directories at all
+    if !exclusions.is_empty() {
+        let exclusions = exclusions.clone();
+        builder.filter_entry(move |entry| {
+            entry.file_name() != ".git" && !exclusions.is_excluded(entry.path())
+        });
+    }
+
+    builder.build().filter_map(Result::ok)
 }
 
-pub fn read_dir(root: &Path, depth: Option<usize>) -> impl Iterator<Item = DirEntry> {
+pub fn read_dir(
+    root: &Path,
+    depth: Option<usize>,
+    exclusions: &Exclusions,
+) -> impl Iterator<Item = DirEntry> {
+    let exclusions = exclusions.clone();
+
     create_walk_builder(root)
         .max_depth(depth)
         .filter_entry(move |entry| match entry.file_type() {
+            _ if exclusions.is_excluded(entry.path()) => false,
             Some(file_type) if file_type.is_dir() => match entry.file_name().to_str() {
                 Some(dir) => !IGNORED_CONTENT_DIRS.contains(&dir),
                 None => false,
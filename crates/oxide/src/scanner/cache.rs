@@ -0,0 +1,478 @@
+//! On-disk cache of the scanner state, so that a new process doesn't have to walk the file system
+//! and extract every single file again.
+//!
+//! The format is a small versioned binary format. Everything is stored as little-endian integers
+//! and length-prefixed UTF-8 strings. If anything about the file looks off, the cache is rejected
+//! as a whole and the scanner starts from scratch.
+use crate::extractor::class_context::ClassContext;
+use crate::extractor::separator::Separator;
+use crate::scanner::file_stamp::{ChangeDetection, FileStamp};
+use crate::{Diagnostic, GlobEntry, PreProcessorMapping};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const MAGIC: &[u8; 8] = b"TWOXIDE\0";
+const VERSION: u32 = 7;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScannerCache {
+    /// The sources the cache was created for. A cache for different sources is never used.
+    pub sources: Option<Vec<GlobEntry>>,
+
+    /// How the stamps were created. Stamps of a different mode never match, so a cache for a
+    /// different mode is never used.
+    pub change_detection: ChangeDetection,
+
+    /// The prefix the candidates were extracted with. A cache for a different prefix is never
+    /// used.
+    pub prefix: Option<String>,
+
+    /// The separator the candidates were extracted with
+    pub separator: Separator,
+
+    /// The class context the candidates were extracted with, if any
+    pub class_context: Option<ClassContext>,
+
+    /// The pre-processor mappings the candidates were extracted with
+    pub pre_processors: Vec<PreProcessorMapping>,
+
+    /// All files we have to scan
+    pub files: Vec<CachedFile>,
+
+    /// All directories we saw during source detection
+    pub dirs: Vec<(PathBuf, Option<FileStamp>)>,
+
+    /// All generated globs
+    pub globs: Vec<GlobEntry>,
+
+    /// Problems found while resolving the sources
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedFile {
+    pub path: PathBuf,
+
+    /// Stamp of the file at the time it was extracted, `None` if it wasn't extracted yet
+    pub stamp: Option<FileStamp>,
+
+    /// Candidates extracted from the file
+    pub candidates: Vec<String>,
+}
+
+impl ScannerCache {
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut w = Writer::default();
+
+        w.buf.extend_from_slice(MAGIC);
+        w.u32(VERSION);
+
+        match &self.sources {
+            Some(sources) => {
+                w.u8(1);
+                w.globs(sources);
+            }
+            None => w.u8(0),
+        }
+
+        w.u8(match self.change_detection {
+            ChangeDetection::Mtime => 0,
+            ChangeDetection::ContentHash => 1,
+        });
+
+        match &self.prefix {
+            Some(prefix) => {
+                w.u8(1);
+                w.str(prefix);
+            }
+            None => w.u8(0),
+        }
+
+        w.str(&self.separator.to_string());
+
+        match &self.class_context {
+            Some(class_context) => {
+                w.u8(1);
+                w.strs(&class_context.attributes);
+                w.strs(&class_context.functions);
+            }
+            None => w.u8(0),
+        }
+
+        w.u64(self.pre_processors.len() as u64);
+        for mapping in &self.pre_processors {
+            w.str(&mapping.pattern);
+            w.strs(&mapping.pre_processors);
+        }
+
+        w.u64(self.files.len() as u64);
+        for file in &self.files {
+            w.path(&file.path)?;
+            w.stamp(&file.stamp);
+            w.u64(file.candidates.len() as u64);
+            for candidate in &file.candidates {
+                w.str(candidate);
+            }
+        }
+
+        w.u64(self.dirs.len() as u64);
+        for (path, stamp) in &self.dirs {
+            w.path(path)?;
+            w.stamp(stamp);
+        }
+
+        w.globs(&self.globs);
+
+        w.u64(self.diagnostics.len() as u64);
+        for diagnostic in &self.diagnostics {
+            match diagnostic {
+                Diagnostic::UnreadableFile { path, message } => {
+                    w.u8(0);
+                    w.path(path)?;
+                    w.str(message);
+                }
+                Diagnostic::MissingSourceBase { base, pattern } => {
+                    w.u8(1);
+                    w.str(base);
+                    w.str(pattern);
+                }
+                Diagnostic::InvalidBraceExpansion { base, pattern } => {
+                    w.u8(2);
+                    w.str(base);
+                    w.str(pattern);
+                }
+                Diagnostic::EmptySource { base, pattern } => {
+                    w.u8(3);
+                    w.str(base);
+                    w.str(pattern);
+                }
+                Diagnostic::InvalidInlineSource { candidate } => {
+                    w.u8(4);
+                    w.str(candidate);
+                }
+                Diagnostic::InvalidSeparator { separator } => {
+                    w.u8(5);
+                    w.str(separator);
+                }
+                Diagnostic::UnknownPreProcessor { pattern, name } => {
+                    w.u8(6);
+                    w.str(pattern);
+                    w.str(name);
+                }
+            }
+        }
+
+        Ok(w.buf)
+    }
+
+    pub fn decode(input: &[u8]) -> io::Result<Self> {
+        let mut r = Reader { input, pos: 0 };
+
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a scanner cache"));
+        }
+
+        if r.u32()? != VERSION {
+            return Err(invalid("unsupported scanner cache version"));
+        }
+
+        let sources = match r.u8()? {
+            0 => None,
+            1 => Some(r.globs()?),
+            _ => return Err(invalid("corrupt scanner cache")),
+        };
+
+        let change_detection = match r.u8()? {
+            0 => ChangeDetection::Mtime,
+            1 => ChangeDetection::ContentHash,
+            _ => return Err(invalid("corrupt scanner cache")),
+        };
+
+        let prefix = match r.u8()? {
+            0 => None,
+            1 => Some(r.str()?),
+            _ => return Err(invalid("corrupt scanner cache")),
+        };
+
+        let separator =
+            Separator::new(&r.str()?).ok_or_else(|| invalid("corrupt scanner cache"))?;
+
+        let class_context = match r.u8()? {
+            0 => None,
+            1 => Some(ClassContext {
+                attributes: r.strs()?,
+                functions: r.strs()?,
+            }),
+            _ => return Err(invalid("corrupt scanner cache")),
+        };
+
+        let mut pre_processors = vec![];
+        for _ in 0..r.u64()? {
+            pre_processors.push(PreProcessorMapping {
+                pattern: r.str()?,
+                pre_processors: r.strs()?,
+            });
+        }
+
+        let mut files = vec![];
+        for _ in 0..r.u64()? {
+            let path = r.path()?;
+            let stamp = r.stamp()?;
+
+            let mut candidates = vec![];
+            for _ in 0..r.u64()? {
+                candidates.push(r.str()?);
+            }
+
+            files.push(CachedFile {
+                path,
+                stamp,
+                candidates,
+            });
+        }
+
+        let mut dirs = vec![];
+        for _ in 0..r.u64()? {
+            dirs.push((r.path()?, r.stamp()?));
+        }
+
+        let globs = r.globs()?;
+
+        let mut diagnostics = vec![];
+        for _ in 0..r.u64()? {
+            diagnostics.push(match r.u8()? {
+                0 => Diagnostic::UnreadableFile {
+                    path: r.path()?,
+                    message: r.str()?,
+                },
+                1 => Diagnostic::MissingSourceBase {
+                    base: r.str()?,
+                    pattern: r.str()?,
+                },
+                2 => Diagnostic::InvalidBraceExpansion {
+                    base: r.str()?,
+                    pattern: r.str()?,
+                },
+                3 => Diagnostic::EmptySource {
+                    base: r.str()?,
+                    pattern: r.str()?,
+                },
+                4 => Diagnostic::InvalidInlineSource {
+                    candidate: r.str()?,
+                },
+                5 => Diagnostic::InvalidSeparator {
+                    separator: r.str()?,
+                },
+                6 => Diagnostic::UnknownPreProcessor {
+                    pattern: r.str()?,
+                    name: r.str()?,
+                },
+                _ => return Err(invalid("corrupt scanner cache")),
+            });
+        }
+
+        if r.pos != input.len() {
+            return Err(invalid("corrupt scanner cache"));
+        }
+
+        Ok(Self {
+            sources,
+            change_detection,
+            prefix,
+            separator,
+            class_context,
+            pre_processors,
+            files,
+            dirs,
+            globs,
+            diagnostics,
+        })
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn strs(&mut self, values: &[String]) {
+        self.u64(values.len() as u64);
+        for value in values {
+            self.str(value);
+        }
+    }
+
+    fn path(&mut self, path: &std::path::Path) -> io::Result<()> {
+        // Paths that are not valid UTF-8 can't be restored reliably, and silently dropping them
+        // would hide them from the scanner forever.
+        let Some(path) = path.to_str() else {
+            return Err(invalid("path is not valid UTF-8"));
+        };
+
+        self.str(path);
+        Ok(())
+    }
+
+    fn stamp(&mut self, stamp: &Option<FileStamp>) {
+        let Some(stamp) = stamp else {
+            self.u8(0);
+            return;
+        };
+
+        let mut flags = 0b001;
+        if stamp.mtime.is_some() {
+            flags |= 0b010;
+        }
+        if stamp.hash.is_some() {
+            flags |= 0b100;
+        }
+        self.u8(flags);
+
+        if let Some(mtime) = stamp.mtime {
+            let mtime = mtime
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+
+            self.u64(mtime.as_secs());
+            self.u32(mtime.subsec_nanos());
+        }
+
+        self.u64(stamp.size);
+
+        if let Some(hash) = stamp.hash {
+            self.u64(hash);
+        }
+    }
+
+    fn globs(&mut self, globs: &[GlobEntry]) {
+        self.u64(globs.len() as u64);
+        for glob in globs {
+            self.str(&glob.base);
+            self.str(&glob.pattern);
+        }
+    }
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.input.len())
+            .ok_or_else(|| invalid("unexpected end of scanner cache"))?;
+
+        let bytes = &self.input[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> io::Result<String> {
+        let len = self.u64()? as usize;
+        let bytes = self.take(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("corrupt scanner cache"))
+    }
+
+    fn strs(&mut self) -> io::Result<Vec<String>> {
+        let mut values = vec![];
+        for _ in 0..self.u64()? {
+            values.push(self.str()?);
+        }
+
+        Ok(values)
+    }
+
+    fn path(&mut self) -> io::Result<PathBuf> {
+        Ok(PathBuf::from(self.str()?))
+    }
+
+    fn stamp(&mut self) -> io::Result<Option<FileStamp>> {
+        let flags = self.u8()?;
+
+        if flags == 0 {
+            return Ok(None);
+        }
+
+        if flags & !0b111 != 0 || flags & 1 == 0 {
+            return Err(invalid("corrupt scanner cache"));
+        }
+
+        let mtime = if flags & 0b010 != 0 {
+            let secs = self.u64()?;
+            let nanos = self.u32()?;
+
+            if nanos >= 1_000_000_000 {
+                return Err(invalid("corrupt scanner cache"));
+            }
+
+            Some(
+                SystemTime::UNIX_EPOCH
+                    .checked_add(Duration::new(secs, nanos))
+                    .ok_or_else(|| invalid("corrupt scanner cache"))?,
+            )
+        } else {
+            None
+        };
+
+        let size = self.u64()?;
+
+        let hash = if flags & 0b100 != 0 {
+            Some(self.u64()?)
+        } else {
+            None
+        };
+
+        Ok(Some(FileStamp { mtime, size, hash }))
+    }
+
+    fn globs(&mut self) -> io::Result<Vec<GlobEntry>> {
+        let mut globs = vec![];
+        for _ in 0..self.u64()? {
+            globs.push(GlobEntry {
+                base: self.str()?,
+                pattern: self.str()?,
+            });
+        }
+        Ok(globs)
+    }
+}
@@ -0,0 +1,210 @@
+use fxhash::{FxHashMap, FxHashSet};
+use std::path::{Path, PathBuf};
+
+/// Tracks which candidates were contributed by which file.
+///
+/// Every candidate is reference counted by the number of sources that contain it. A source is
+/// either a file on disk, or raw content that was passed to the scanner directly. Raw content
+/// doesn't have an identity, therefore its candidates are never released.
+///
+/// When a file changes, its previous set of candidates is swapped for the new set. Candidates
+/// that are no longer referenced by any source are dropped from the index.
+#[derive(Debug, Clone, Default)]
+pub struct CandidateIndex {
+    /// Number of sources that contain a candidate
+    counts: FxHashMap<String, usize>,
+
+    /// Candidates per file
+    files: FxHashMap<PathBuf, FxHashSet<String>>,
+
+    /// Candidates from raw content that isn't backed by a file
+    content: FxHashSet<String>,
+
+    /// Candidates that are never part of the index, regardless of where they are found
+    excluded: FxHashSet<String>,
+
+    /// Changes since the last call to `take_changes`. We only start tracking changes once they
+    /// are requested for the first time.
+    changes: Option<Changes>,
+}
+
+/// Changes to the index since the last time they were requested.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Changes {
+    /// Candidates that were added to the index
+    pub added: FxHashSet<String>,
+
+    /// Candidates that were removed from the index
+    pub removed: FxHashSet<String>,
+
+    /// Files that were updated or removed
+    pub files: FxHashSet<PathBuf>,
+}
+
+impl CandidateIndex {
+    /// Replace the candidates of a file. Returns the candidates that are new to the index.
+    pub fn set_file(&mut self, path: PathBuf, candidates: FxHashSet<String>) -> Vec<String> {
+        if let Some(changes) = &mut self.changes {
+            changes.files.insert(path.clone());
+        }
+
+        let previous = self.files.remove(&path).unwrap_or_default();
+
+        let mut added = vec![];
+        for candidate in candidates.difference(&previous) {
+            if self.retain(candidate) {
+                added.push(candidate.clone());
+            }
+        }
+
+        for candidate in previous.difference(&candidates) {
+            self.release(candidate);
+        }
+
+        self.files.insert(path, candidates);
+
+        added
+    }
+
+    /// Forget a file and release all of its candidates.
+    pub fn remove_file(&mut self, path: &Path) {
+        let Some(previous) = self.files.remove(path) else {
+            return;
+        };
+
+        if let Some(changes) = &mut self.changes {
+            changes.files.insert(path.to_path_buf());
+        }
+
+        for candidate in &previous {
+            self.release(candidate);
+        }
+    }
+
+    /// Add candidates from raw content. Returns the candidates that are new to the index.
+    pub fn add_content(&mut self, candidates: impl IntoIterator<Item = String>) -> Vec<String> {
+        let mut added = vec![];
+
+        for candidate in candidates {
+            if self.content.contains(&candidate) {
+                continue;
+            }
+
+            if self.retain(&candidate) {
+                added.push(candidate.clone());
+            }
+
+            self.content.insert(candidate);
+        }
+
+        added
+    }
+
+    /// Drop candidates from the index, and never add them again.
+    pub fn exclude(&mut self, candidates: impl IntoIterator<Item = String>) {
+        for candidate in candidates {
+            if self.counts.remove(&candidate).is_some() {
+                if let Some(changes) = &mut self.changes {
+                    if !changes.added.remove(&candidate) {
+                        changes.removed.insert(candidate.clone());
+                    }
+                }
+            }
+
+            self.excluded.insert(candidate);
+        }
+    }
+
+    pub fn is_excluded(&self, candidate: &str) -> bool {
+        self.excluded.contains(candidate)
+    }
+
+    pub fn contains(&self, candidate: &str) -> bool {
+        self.counts.contains_key(candidate)
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// All candidates that are currently referenced by at least one source.
+    pub fn candidates(&self) -> impl Iterator<Item = &String> {
+        self.counts.keys()
+    }
+
+    /// All files that contributed candidates.
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Candidates of a single file (if the file is known).
+    pub fn file_candidates(&self, path: &Path) -> Option<&FxHashSet<String>> {
+        self.files.get(path)
+    }
+
+    /// Take all changes since the last call. The first call reports the full index as added.
+    pub fn take_changes(&mut self) -> Changes {
+        match self.changes.replace(Changes::default()) {
+            Some(changes) => changes,
+            None => Changes {
+                added: self.counts.keys().cloned().collect(),
+                removed: FxHashSet::default(),
+                files: self.files.keys().cloned().collect(),
+            },
+        }
+    }
+
+    /// Increment the reference count. Returns `true` if the candidate is new.
+    fn retain(&mut self, candidate: &str) -> bool {
+        if self.excluded.contains(candidate) {
+            return false;
+        }
+
+        match self.counts.get_mut(candidate) {
+            Some(count) => {
+                *count += 1;
+                false
+            }
+            None => {
+                self.counts.insert(candidate.to_owned(), 1);
+
+                if let Some(changes) = &mut self.changes {
+                    // Removed and added again, so nothing changed
+                    if !changes.removed.remove(candidate) {
+                        changes.added.insert(candidate.to_owned());
+                    }
+                }
+
+                true
+            }
+        }
+    }
+
+    /// Decrement the reference count. Returns `true` if the candidate is gone.
+    fn release(&mut self, candidate: &str) -> bool {
+        let Some(count) = self.counts.get_mut(candidate) else {
+            return false;
+        };
+
+        *count -= 1;
+
+        if *count == 0 {
+            self.counts.remove(candidate);
+
+            if let Some(changes) = &mut self.changes {
+                // Added and removed again, so nothing changed
+                if !changes.added.remove(candidate) {
+                    changes.removed.insert(candidate.to_owned());
+                }
+            }
+
+            return true;
+        }
+
+        false
+    }
+}
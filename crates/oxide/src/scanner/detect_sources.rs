@@ -1,3 +1,4 @@
+use crate::glob::Exclusions;
 use crate::scanner::allowed_paths::{is_allowed_content_path, resolve_allowed_paths};
 use crate::GlobEntry;
 use fxhash::FxHashSet;
@@ -9,6 +10,9 @@ use walkdir::WalkDir;
 #[derive(Debug, Clone)]
 pub struct DetectSources {
     base: PathBuf,
+
+    /// Paths that should never be detected, e.g. from `!src/legacy/**`
+    exclusions: Exclusions,
 }
 
 static KNOWN_EXTENSIONS: sync::LazyLock<Vec<&'static str>> = sync::LazyLock::new(|| {
@@ -24,7 +28,17 @@ static KNOWN_EXTENSIONS: sync::LazyLock<Vec<&'static str>> = sync::LazyLock::new
 
 impl DetectSources {
     pub fn new(base: PathBuf) -> Self {
-        Self { base }
+        Self {
+            base,
+            exclusions: Exclusions::default(),
+        }
+    }
+
+    /// Skip excluded files and directories. Excluded directories are treated like ignored
+    /// directories, so the generated globs won't cover them either.
+    pub fn with_exclusions(mut self, exclusions: Exclusions) -> Self {
+        self.exclusions = exclusions;
+        self
     }
 
     pub fn detect(&self) -> (Vec<PathBuf>, Vec<GlobEntry>, Vec<PathBuf>) {
@@ -38,7 +52,7 @@ impl DetectSources {
         let mut files: Vec<PathBuf> = vec![];
         let mut dirs: Vec<PathBuf> = vec![];
 
-        for entry in resolve_allowed_paths(&self.base) {
+        for entry in resolve_allowed_paths(&self.base, &self.exclusions) {
             let Some(file_type) = entry.file_type() else {
                 continue;
             };
@@ -0,0 +1,102 @@
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// How the scanner decides whether a file or directory changed since it was last scanned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangeDetection {
+    /// Compare modification times and sizes. This is cheap, but relies on timestamps being
+    /// reliable.
+    #[default]
+    Mtime,
+
+    /// Compare sizes and a hash of the contents. For directories, the names of the entries are
+    /// hashed. This requires reading every file, but works regardless of timestamps, e.g. on CI
+    /// checkouts, Docker layer copies and network file systems.
+    ContentHash,
+}
+
+/// Cheap fingerprint of a file or directory, used to detect whether it changed since we last saw
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    /// Last modification time, only tracked in [`ChangeDetection::Mtime`] mode
+    pub mtime: Option<SystemTime>,
+
+    /// Size in bytes (or the amount of entries for directories)
+    pub size: u64,
+
+    /// Hash of the contents, only tracked in [`ChangeDetection::ContentHash`] mode
+    pub hash: Option<u64>,
+}
+
+impl FileStamp {
+    /// Read the stamp of a file. Fails if the file doesn't exist (anymore), or if its metadata
+    /// can't be read.
+    pub fn read(path: &Path, mode: ChangeDetection) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+
+        Ok(match mode {
+            ChangeDetection::Mtime => Self {
+                mtime: Some(metadata.modified().unwrap_or(SystemTime::now())),
+                size: metadata.len(),
+                hash: None,
+            },
+
+            // If the file exists but can't be read, the stamp only consists of the size. Reading
+            // the file for extraction will fail as well and report the problem.
+            ChangeDetection::ContentHash => Self {
+                mtime: None,
+                size: metadata.len(),
+                hash: fs::read(path).ok().map(|content| hash_bytes(&content)),
+            },
+        })
+    }
+
+    /// The stamp of a file in [`ChangeDetection::ContentHash`] mode, for contents that were
+    /// already read
+    pub fn from_content(content: &[u8]) -> Self {
+        Self {
+            mtime: None,
+            size: content.len() as u64,
+            hash: Some(hash_bytes(content)),
+        }
+    }
+
+    /// Read the stamp of a directory. Returns `None` if the directory doesn't exist (anymore).
+    pub fn read_dir(path: &Path, mode: ChangeDetection) -> Option<Self> {
+        match mode {
+            ChangeDetection::Mtime => Self::read(path, mode).ok(),
+
+            // A directory changes when entries are added, removed or renamed, so we hash the
+            // sorted names of its entries.
+            ChangeDetection::ContentHash => {
+                let mut names = fs::read_dir(path)
+                    .ok()?
+                    .filter_map(|entry| entry.ok().map(|entry| entry.file_name()))
+                    .collect::<Vec<_>>();
+                names.sort_unstable();
+
+                let mut hasher = fxhash::FxHasher::default();
+                for name in &names {
+                    hasher.write(name.as_encoded_bytes());
+                    hasher.write_u8(0);
+                }
+
+                Some(Self {
+                    mtime: None,
+                    size: names.len() as u64,
+                    hash: Some(hasher.finish()),
+                })
+            }
+        }
+    }
+}
+
+fn hash_bytes(content: &[u8]) -> u64 {
+    let mut hasher = fxhash::FxHasher::default();
+    hasher.write(content);
+    hasher.finish()
+}
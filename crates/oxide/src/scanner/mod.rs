@@ -1,2 +1,7 @@
 pub mod allowed_paths;
+pub mod cache;
+pub mod candidate_index;
 pub mod detect_sources;
+pub mod file_stamp;
+pub mod multi_line;
+pub mod watcher;
@@ -0,0 +1,116 @@
+//! Candidates with arbitrary values can be wrapped over multiple lines by formatters, e.g.:
+//!
+//! ```html
+//! <div class="grid-cols-[repeat(auto-fill,
+//!   minmax(200px,1fr))]"></div>
+//! ```
+//!
+//! Blobs are extracted line by line, so these candidates would never be seen as a whole. When the
+//! last token of a line still has an open `[`, we stitch the following lines onto it (without the
+//! line break and the indentation) and extract the result again.
+use crate::extractor::candidate_parser::normalize_separator;
+use crate::extractor::{Extracted, Extractor, ExtractorOptions};
+use bstr::ByteSlice;
+
+/// Maximum amount of lines a single candidate can span
+const MAX_LINES: usize = 8;
+
+/// Extract the candidates that start on line `idx`, but continue on the next line(s). Returns
+/// each candidate together with its byte offset in line `idx`.
+///
+/// Candidates that fit on a single line are not returned, they are found by extracting the line
+/// itself.
+pub fn extract_multi_line(
+    lines: &[&[u8]],
+    idx: usize,
+    options: &ExtractorOptions,
+) -> Vec<(Vec<u8>, usize)> {
+    let line = lines[idx].trim_end();
+
+    // Candidates can't contain whitespace, so only the last token of the line can continue on the
+    // next line. We keep the whitespace in front of it, because it acts as a boundary.
+    let start = line
+        .iter()
+        .rposition(|x| x.is_ascii_whitespace())
+        .unwrap_or(0);
+    let token = &line[start..];
+
+    let mut depth = bracket_depth(token, 0);
+    if depth == 0 {
+        return vec![];
+    }
+
+    let mut buffer = token.to_vec();
+    let mut joints = vec![];
+
+    for line in lines.iter().skip(idx + 1).take(MAX_LINES - 1) {
+        let line = line.trim_start();
+
+        // An empty line always ends a candidate
+        if line.is_empty() {
+            break;
+        }
+
+        joints.push(buffer.len());
+
+        // Everything after the first whitespace can't be part of the same candidate
+        match line.iter().position(|x| x.is_ascii_whitespace()) {
+            Some(end) => {
+                buffer.extend_from_slice(&line[..=end]);
+                break;
+            }
+            None => buffer.extend_from_slice(line),
+        }
+
+        depth = bracket_depth(line, depth);
+        if depth == 0 {
+            break;
+        }
+    }
+
+    let Some(first_joint) = joints.first().copied() else {
+        return vec![];
+    };
+
+    let offset = buffer.as_ptr() as usize;
+
+    Extractor::with_options(&buffer, options)
+        .extract()
+        .into_iter()
+        .filter_map(|extracted| {
+            let (bytes, is_candidate) = match extracted {
+                Extracted::Candidate(bytes) => (bytes, true),
+                Extracted::CssVariable(bytes) => (bytes, false),
+            };
+
+            let from = bytes.as_ptr() as usize - offset;
+            let to = from + bytes.len();
+
+            // Starts on a later line, or doesn't cross a line break at all
+            if from >= first_joint || !joints.iter().any(|joint| from < *joint && *joint < to) {
+                return None;
+            }
+
+            if is_candidate {
+                return Some((
+                    normalize_separator(bytes, options.separator).into_owned(),
+                    start + from,
+                ));
+            }
+
+            Some((bytes.to_vec(), start + from))
+        })
+        .collect()
+}
+
+fn bracket_depth(input: &[u8], mut depth: usize) -> usize {
+    for x in input {
+        match x {
+            b'[' => depth += 1,
+            b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    depth
+}
@@ -0,0 +1,278 @@
+//! Native file system watcher for the scanner.
+//!
+//! The watcher observes directories and reports every path that changed inside of them. Changes
+//! are collected into batches: the first change opens a window of `debounce`, and every change
+//! that happens within that window ends up in the same batch. This keeps editors that write files
+//! in multiple steps (write to a temporary file, rename, touch, ...) from triggering multiple
+//! scans.
+//!
+//! Only Linux (inotify) is supported for now. On other platforms creating a watcher fails with
+//! [`io::ErrorKind::Unsupported`].
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+/// A debounced set of changes reported by the [`Watcher`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WatchBatch {
+    /// Files and directories that were created, modified, removed or renamed
+    pub paths: Vec<PathBuf>,
+
+    /// The watcher lost track of some changes (e.g. because the kernel queue overflowed), so
+    /// everything should be checked again.
+    pub rescan: bool,
+}
+
+pub struct Watcher {
+    #[cfg(target_os = "linux")]
+    inner: std::sync::Arc<inotify::Inotify>,
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watcher").finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Watcher {
+    /// Start a new watcher. Batches are delivered on the returned channel until the watcher is
+    /// dropped.
+    pub fn new(debounce: Duration) -> io::Result<(Self, Receiver<WatchBatch>)> {
+        let (inner, receiver) = inotify::Inotify::start(debounce)?;
+
+        Ok((Self { inner }, receiver))
+    }
+
+    /// Watch the direct entries of a directory. Sub-directories have to be watched separately.
+    pub fn watch(&self, dir: &Path) -> io::Result<()> {
+        self.inner.add_watch(dir)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    pub fn new(_debounce: Duration) -> io::Result<(Self, Receiver<WatchBatch>)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "file watching is not supported on this platform",
+        ))
+    }
+
+    pub fn watch(&self, _dir: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.inner.stop();
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use super::WatchBatch;
+    use fxhash::{FxHashMap, FxHashSet};
+    use std::ffi::{CString, OsStr};
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    const WATCH_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MODIFY
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE_SELF
+        | libc::IN_ONLYDIR;
+
+    /// How often the background thread checks whether it should stop, while it's idle.
+    const IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+    const EVENT_SIZE: usize = std::mem::size_of::<libc::inotify_event>();
+
+    pub struct Inotify {
+        fd: OwnedFd,
+
+        /// Watched directory per watch descriptor
+        watches: Mutex<FxHashMap<i32, PathBuf>>,
+
+        stopped: AtomicBool,
+    }
+
+    impl Inotify {
+        pub fn start(debounce: Duration) -> io::Result<(Arc<Self>, Receiver<WatchBatch>)> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let inotify = Arc::new(Self {
+                fd: unsafe { OwnedFd::from_raw_fd(fd) },
+                watches: Mutex::new(FxHashMap::default()),
+                stopped: AtomicBool::new(false),
+            });
+
+            let (sender, receiver) = mpsc::channel();
+
+            let thread_inotify = inotify.clone();
+            std::thread::Builder::new()
+                .name("tailwindcss-watcher".into())
+                .spawn(move || thread_inotify.run(debounce, sender))?;
+
+            Ok((inotify, receiver))
+        }
+
+        pub fn add_watch(&self, dir: &Path) -> io::Result<()> {
+            let path = CString::new(dir.as_os_str().as_bytes())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+            let wd =
+                unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), path.as_ptr(), WATCH_MASK) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            self.watches.lock().unwrap().insert(wd, dir.to_path_buf());
+
+            Ok(())
+        }
+
+        pub fn stop(&self) {
+            self.stopped.store(true, Ordering::Relaxed);
+        }
+
+        fn run(&self, debounce: Duration, sender: Sender<WatchBatch>) {
+            let mut buffer = vec![0u8; 64 * 1024];
+            let mut pending = FxHashSet::default();
+            let mut rescan = false;
+            let mut window_end: Option<Instant> = None;
+
+            while !self.stopped.load(Ordering::Relaxed) {
+                let timeout = match window_end {
+                    Some(end) => end.saturating_duration_since(Instant::now()),
+                    None => IDLE_TIMEOUT,
+                };
+
+                if self.wait(timeout) {
+                    let Ok(len) = self.read(&mut buffer) else {
+                        return;
+                    };
+
+                    rescan |= self.parse(&buffer[..len], &mut pending);
+
+                    if window_end.is_none() && (rescan || !pending.is_empty()) {
+                        window_end = Some(Instant::now() + debounce);
+                    }
+                }
+
+                if window_end.is_some_and(|end| Instant::now() >= end) {
+                    let mut paths: Vec<_> = pending.drain().collect();
+                    paths.sort_unstable();
+
+                    let batch = WatchBatch { paths, rescan };
+                    rescan = false;
+                    window_end = None;
+
+                    // Nobody is listening anymore
+                    if sender.send(batch).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        /// Wait until events are available. Returns `false` if the timeout expired.
+        fn wait(&self, timeout: Duration) -> bool {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+            let result = unsafe { libc::poll(&mut pollfd, 1, timeout) };
+
+            result > 0 && pollfd.revents & libc::POLLIN != 0
+        }
+
+        fn read(&self, buffer: &mut [u8]) -> io::Result<usize> {
+            let len = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                )
+            };
+
+            if len >= 0 {
+                return Ok(len as usize);
+            }
+
+            let err = io::Error::last_os_error();
+            match err.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => Ok(0),
+                _ => Err(err),
+            }
+        }
+
+        /// Collect the changed paths of all events in the buffer. Returns `true` if events were
+        /// lost.
+        fn parse(&self, mut buffer: &[u8], pending: &mut FxHashSet<PathBuf>) -> bool {
+            let mut rescan = false;
+            let mut watches = self.watches.lock().unwrap();
+
+            while buffer.len() >= EVENT_SIZE {
+                // The buffer is not guaranteed to be aligned for `inotify_event`
+                let event = unsafe {
+                    std::ptr::read_unaligned(buffer.as_ptr() as *const libc::inotify_event)
+                };
+
+                let name_len = event.len as usize;
+                let Some(name) = buffer.get(EVENT_SIZE..EVENT_SIZE + name_len) else {
+                    break;
+                };
+                buffer = &buffer[EVENT_SIZE + name_len..];
+
+                if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                    rescan = true;
+                    continue;
+                }
+
+                // The directory itself is gone, so the kernel dropped the watch
+                if event.mask & libc::IN_IGNORED != 0 {
+                    if let Some(dir) = watches.remove(&event.wd) {
+                        pending.insert(dir);
+                    }
+                    continue;
+                }
+
+                let Some(dir) = watches.get(&event.wd) else {
+                    continue;
+                };
+
+                // The name is padded with NUL bytes
+                let name = name.split(|b| *b == 0).next().unwrap_or_default();
+
+                if name.is_empty() {
+                    pending.insert(dir.clone());
+                } else {
+                    pending.insert(dir.join(OsStr::from_bytes(name)));
+                }
+            }
+
+            rescan
+        }
+    }
+}
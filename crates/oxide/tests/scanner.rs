@@ -22,6 +22,25 @@ mod scanner {
         }
     }
 
+    /// Create a git repository with the given files, and a source that covers all of them
+    fn create_project(paths: &[(&str, &str)]) -> (path::PathBuf, Vec<GlobEntry>) {
+        // Create a temporary working directory
+        let dir = tempdir().unwrap().into_path();
+
+        // Initialize this directory as a git repository
+        let _ = Command::new("git").arg("init").current_dir(&dir).output();
+
+        // Create files
+        create_files_in(&dir, paths);
+
+        let sources = vec![GlobEntry {
+            base: dir.to_string_lossy().to_string(),
+            pattern: "**/*".to_owned(),
+        }];
+
+        (dir, sources)
+    }
+
     fn scan_with_globs(
         paths_with_content: &[(&str, &str)],
         globs: Vec<&str>,
@@ -221,6 +240,323 @@ mod scanner {
         assert_eq!(globs, vec!["*", "index.html"]);
     }
 
+    #[test]
+    fn it_should_exclude_files_matching_negated_sources() {
+        let (paths, candidates) = scan_with_globs(
+            &[
+                ("index.html", "content-['index.html']"),
+                ("src/a.tsx", "content-['src/a.tsx']"),
+                ("src/a.stories.tsx", "content-['src/a.stories.tsx']"),
+                ("src/legacy/b.html", "content-['src/legacy/b.html']"),
+                (
+                    "src/legacy/nested/c.html",
+                    "content-['src/legacy/nested/c.html']",
+                ),
+            ],
+            vec!["!src/legacy", "!**/*.stories.tsx"],
+        );
+
+        assert_eq!(
+            candidates,
+            vec!["content-['index.html']", "content-['src/a.tsx']"]
+        );
+        assert_eq!(
+            paths,
+            vec![
+                "!{**/*.stories.tsx,src/legacy}",
+                "*",
+                "index.html",
+                "src/*/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "src/a.tsx",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_add_and_exclude_inline_candidates() {
+        let (_, sources) = create_project(&[("index.html", "underline flex bg-red-500")]);
+
+        let mut scanner = Scanner::new(Some(sources)).with_inline_sources(vec![
+            InlineSource {
+                candidates: "bg-{red,blue}-{100..300..100} shadow".to_owned(),
+                negated: false,
+            },
+            InlineSource {
+                candidates: "bg-blue-200 underline invalid-{".to_owned(),
+                negated: true,
+            },
+            InlineSource {
+                candidates: "flex bg-red-{400,500}".to_owned(),
+                negated: true,
+            },
+        ]);
+
+        assert_eq!(
+            scanner.scan(),
+            vec![
+                "bg-blue-100",
+                "bg-blue-300",
+                "bg-red-100",
+                "bg-red-200",
+                "bg-red-300",
+                "shadow",
+            ]
+        );
+
+        assert_eq!(
+            scanner.get_diagnostics(),
+            vec![Diagnostic::InvalidInlineSource {
+                candidate: "invalid-{".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_only_extract_prefixed_candidates() {
+        let (dir, sources) = create_project(&[
+            (
+                "index.html",
+                r#"<div class="tw:flex tw:hover:underline grid hover:tw:block">Some text</div>"#,
+            ),
+            (
+                "app.js",
+                "const styles = { gap: 'var(--gap)', className: 'tw:grid-cols-[repeat(2,\n  1fr)]' }",
+            ),
+        ]);
+
+        let mut scanner = Scanner::new(Some(sources.clone())).with_prefix("tw:".to_owned());
+
+        assert_eq!(
+            scanner.scan(),
+            vec![
+                "--gap",
+                "tw:flex",
+                "tw:grid-cols-[repeat(2,1fr)]",
+                "tw:hover:underline",
+            ]
+        );
+
+        assert_eq!(
+            scanner.scan_content(vec![ChangedContent::Content(
+                "tw:p-4 p-4".to_owned(),
+                "html".into()
+            )]),
+            vec!["tw:p-4"]
+        );
+
+        // A cache created with a different prefix is not used
+        let cache = dir.join("scanner.cache");
+        scanner.save_cache(&cache).unwrap();
+
+        let mut scanner = Scanner::new(Some(sources));
+        assert!(!scanner.load_cache(&cache).unwrap());
+        assert!(scanner.scan().contains(&"grid".to_owned()));
+    }
+
+    #[test]
+    fn it_should_normalize_candidates_with_a_custom_separator() {
+        let (dir, sources) = create_project(&[
+            (
+                "index.html",
+                r#"<div class="flex md__hover__underline [&_p]__p-4 grid-cols-[1fr_auto]"></div>"#,
+            ),
+            (
+                "app.elm",
+                "div [ class \"group-hover/card__bg-red-500/50\" ] []",
+            ),
+        ]);
+
+        let mut scanner = Scanner::new(Some(sources.clone())).with_separator("__".to_owned());
+
+        let candidates = scanner.scan();
+        for candidate in [
+            "flex",
+            "md:hover:underline",
+            "[&_p]:p-4",
+            "grid-cols-[1fr_auto]",
+            "group-hover/card:bg-red-500/50",
+        ] {
+            assert!(
+                candidates.contains(&candidate.to_owned()),
+                "missing {candidate} in {candidates:?}"
+            );
+        }
+        assert!(!candidates.iter().any(|x| x.contains("__")));
+
+        // Locations point to the original candidate
+        let locations = scanner.get_candidate_locations();
+        let (_, underline) = locations
+            .iter()
+            .find(|(x, _)| x == "md:hover:underline")
+            .unwrap();
+        assert_eq!((underline[0].line, underline[0].column), (1, 18));
+
+        // A cache created with a different separator is not used
+        let cache = dir.join("scanner.cache");
+        scanner.save_cache(&cache).unwrap();
+
+        let mut scanner = Scanner::new(Some(sources));
+        assert!(!scanner.load_cache(&cache).unwrap());
+
+        // Invalid separators are reported
+        let mut scanner = Scanner::new(None).with_separator("-".to_owned());
+        assert_eq!(
+            scanner.get_diagnostics(),
+            vec![Diagnostic::InvalidSeparator {
+                separator: "-".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_only_extract_candidates_inside_of_class_contexts() {
+        let (_, sources) = create_project(&[
+            (
+                "README.md",
+                "# Usage\n\nAdd <span class=\"font-bold\">flex</span> to the element.",
+            ),
+            (
+                "src/button.tsx",
+                "export function Button({ isActive }) {\n  return <button className={clsx(\n    'px-4 py-2',\n    isActive && 'bg-blue-500',\n  )} />\n}",
+            ),
+            ("src/card.vue", r#"<div wrapperClass="shadow-lg">card</div>"#),
+        ]);
+
+        let mut class_context = ClassContext::default();
+        class_context.attributes.push("wrapperClass".into());
+
+        let mut scanner = Scanner::new(Some(sources)).with_class_context(class_context);
+
+        assert_eq!(
+            scanner.scan(),
+            vec!["bg-blue-500", "font-bold", "px-4", "py-2", "shadow-lg"]
+        );
+
+        // Locations still point to the original content
+        let locations = scanner.get_candidate_locations();
+        let (_, bg) = locations.iter().find(|(x, _)| x == "bg-blue-500").unwrap();
+        assert_eq!((bg[0].line, bg[0].column), (4, 18));
+
+        // CSS variables are still extracted outside of class contexts
+        let mut scanner = Scanner::new(None).with_class_context(ClassContext::default());
+        assert_eq!(
+            scanner.scan_content(vec![ChangedContent::Content(
+                r#"<p class="flex" style="color: var(--brand)">Some text</p>"#.into(),
+                "html".into(),
+            )]),
+            vec!["--brand", "flex"]
+        );
+    }
+
+    #[test]
+    fn it_should_warn_about_dynamic_classes() {
+        let (dir, sources) = create_project(&[
+            (
+                "src/button.tsx",
+                "export function Button({ color, size }) {\n  return <button className={`flex bg-${color}-500 ${'text-' + size}`} />\n}",
+            ),
+            ("src/card.php", "<div class=\"p-4 shadow-{$shadow}\"></div>"),
+            ("src/index.html", "<div class=\"flex underline\"></div>"),
+            // Not checked
+            ("src/index.css", "/* bg-${color}-500 */"),
+            ("src/data.json", "{ \"class\": \"bg-${color}-500\" }"),
+        ]);
+
+        let mut scanner = Scanner::new(Some(sources));
+
+        let warnings = scanner.get_dynamic_class_warnings();
+        let actual: Vec<_> = warnings
+            .iter()
+            .map(|warning| {
+                (
+                    warning
+                        .location
+                        .file
+                        .strip_prefix(&*dir.to_string_lossy())
+                        .unwrap(),
+                    warning.location.line,
+                    warning.location.column,
+                    warning.source.as_str(),
+                    warning.kind,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    "/src/button.tsx",
+                    2,
+                    35,
+                    "bg-${color}-500",
+                    DynamicClassKind::Interpolation
+                ),
+                (
+                    "/src/button.tsx",
+                    2,
+                    54,
+                    "text-",
+                    DynamicClassKind::Concatenation
+                ),
+                (
+                    "/src/card.php",
+                    1,
+                    17,
+                    "shadow-{$shadow}",
+                    DynamicClassKind::Interpolation
+                ),
+            ]
+        );
+
+        assert!(warnings[0]
+            .to_string()
+            .ends_with("/src/button.tsx:2:35: Class `bg-${color}-500` is interpolated and can't be detected, use complete class names instead"));
+    }
+
+    #[test]
+    fn it_should_use_configured_pre_processors() {
+        let (_, sources) = create_project(&[
+            (
+                "app/views/show.json.jbuilder",
+                "json.classes %w[bg-red-500]",
+            ),
+            ("app/views/index.tpl", ".flex.p-4\n= %w[underline]"),
+        ]);
+
+        let candidates = Scanner::new(Some(sources.clone())).scan();
+        assert!(!candidates.contains(&"bg-red-500".to_owned()));
+        assert!(!candidates.contains(&"underline".to_owned()));
+
+        let mut scanner = Scanner::new(Some(sources)).with_pre_processors(vec![
+            PreProcessorMapping {
+                pattern: "jbuilder".to_owned(),
+                pre_processors: vec!["ruby".to_owned()],
+            },
+            PreProcessorMapping {
+                pattern: "app/views/*.tpl".to_owned(),
+                pre_processors: vec!["slim".to_owned(), "ruby".to_owned(), "erb".to_owned()],
+            },
+        ]);
+
+        let candidates = scanner.scan();
+        for candidate in ["bg-red-500", "flex", "p-4", "underline"] {
+            assert!(
+                candidates.contains(&candidate.to_owned()),
+                "missing {candidate} in {candidates:?}"
+            );
+        }
+
+        // Unknown pre-processors are reported
+        assert_eq!(
+            scanner.get_diagnostics(),
+            vec![Diagnostic::UnknownPreProcessor {
+                pattern: "app/views/*.tpl".to_owned(),
+                name: "erb".to_owned(),
+            }]
+        );
+    }
+
     #[test]
     fn it_should_ignore_and_expand_nested_ignored_folders() {
         let globs = test(&[
@@ -632,6 +968,724 @@ mod scanner {
         );
     }
 
+    #[test]
+    fn it_should_remove_candidates_of_changed_files() {
+        let (dir, sources) = create_project(&[
+            ("a.html", "content-['a.html'] shared"),
+            ("b.html", "content-['b.html'] shared"),
+        ]);
+
+        let mut scanner = Scanner::new(Some(sources));
+        let candidates = scanner.scan();
+
+        assert_eq!(
+            candidates,
+            vec![
+                "content-['a.html']".to_owned(),
+                "content-['b.html']".to_owned(),
+                "shared".to_owned(),
+            ]
+        );
+
+        // We have to sleep because it might run too fast (seriously) and the
+        // mtimes of the files end up being the same as the last time we
+        // checked them
+        sleep(Duration::from_millis(100));
+
+        // `shared` is still used by `b.html`, so it should be kept
+        create_files_in(&dir, &[("a.html", "content-['a.html/changed']")]);
+
+        let candidates = scanner.scan();
+
+        assert_eq!(
+            candidates,
+            vec![
+                "content-['a.html/changed']".to_owned(),
+                "content-['b.html']".to_owned(),
+                "shared".to_owned(),
+            ]
+        );
+
+        sleep(Duration::from_millis(100));
+
+        // `shared` is not used by any file anymore
+        create_files_in(&dir, &[("b.html", "content-['b.html']")]);
+
+        let candidates = scanner.scan();
+
+        assert_eq!(
+            candidates,
+            vec![
+                "content-['a.html/changed']".to_owned(),
+                "content-['b.html']".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_remove_candidates_of_deleted_files() {
+        let (dir, sources) = create_project(&[
+            ("a.html", "content-['a.html'] shared"),
+            ("nested/b.html", "content-['nested/b.html'] shared"),
+        ]);
+
+        let mut scanner = Scanner::new(Some(sources));
+        let candidates = scanner.scan();
+
+        assert_eq!(
+            candidates,
+            vec![
+                "content-['a.html']".to_owned(),
+                "content-['nested/b.html']".to_owned(),
+                "shared".to_owned(),
+            ]
+        );
+
+        fs::remove_file(dir.join("nested/b.html")).unwrap();
+
+        let candidates = scanner.scan();
+
+        assert_eq!(
+            candidates,
+            vec!["content-['a.html']".to_owned(), "shared".to_owned()]
+        );
+        assert_eq!(scanner.get_files().len(), 1);
+
+        fs::remove_file(dir.join("a.html")).unwrap();
+
+        let candidates = scanner.scan();
+
+        assert!(candidates.is_empty());
+        assert!(scanner.get_files().is_empty());
+    }
+
+    #[test]
+    fn it_should_replace_candidates_of_changed_files_when_scanning_content() {
+        // Create a temporary working directory
+        let dir = tempdir().unwrap().into_path();
+
+        // Create files
+        create_files_in(&dir, &[("index.html", "content-['index.html']")]);
+
+        let path = dir.join("index.html");
+
+        let mut scanner = Scanner::new(None);
+        let new_candidates = scanner.scan_content(vec![
+            ChangedContent::File(path.clone(), "html".into()),
+            ChangedContent::Content("content-['raw']".into(), "html".into()),
+        ]);
+
+        assert_eq!(
+            new_candidates,
+            vec![
+                "content-['index.html']".to_owned(),
+                "content-['raw']".to_owned(),
+            ]
+        );
+
+        create_files_in(&dir, &[("index.html", "content-['index.html/changed']")]);
+
+        let new_candidates =
+            scanner.scan_content(vec![ChangedContent::File(path.clone(), "html".into())]);
+
+        assert_eq!(
+            new_candidates,
+            vec!["content-['index.html/changed']".to_owned()]
+        );
+
+        // Candidates from raw content are never removed
+        assert_eq!(
+            scanner.scan(),
+            vec![
+                "content-['index.html/changed']".to_owned(),
+                "content-['raw']".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_remove_candidates_of_deleted_files_when_scanning_content() {
+        // Create a temporary working directory
+        let dir = tempdir().unwrap().into_path();
+
+        // Create files
+        create_files_in(&dir, &[("index.html", "content-['index.html']")]);
+
+        let mut scanner = Scanner::new(None);
+        scanner.scan_content(vec![
+            ChangedContent::File(dir.join("index.html"), "html".into()),
+            ChangedContent::Content("content-['raw']".into(), "html".into()),
+        ]);
+        scanner.scan_changes();
+
+        fs::remove_file(dir.join("index.html")).unwrap();
+
+        let changes = scanner.scan_changes();
+        assert_eq!(changes.added, Vec::<String>::new());
+        assert_eq!(changes.removed, vec!["content-['index.html']".to_owned()]);
+        assert_eq!(scanner.scan(), vec!["content-['raw']".to_owned()]);
+    }
+
+    #[test]
+    fn it_should_find_all_locations_of_candidates() {
+        let (dir, sources) = create_project(&[
+            (
+                "a.html",
+                "<div class=\"flex\">\n  <span class=\"underline flex\">",
+            ),
+            ("b.html", "🔥 flex"),
+        ]);
+
+        let mut scanner = Scanner::new(Some(sources));
+        let locations = scanner.get_candidate_locations();
+
+        let base = format!("{}", dunce::canonicalize(&dir).unwrap().display()).replace('\\', "/");
+        let a = format!("{}/a.html", base);
+        let b = format!("{}/b.html", base);
+
+        let location = |file: &str, line: usize, column: usize| CandidateLocation {
+            file: file.to_owned(),
+            line,
+            column,
+        };
+
+        assert_eq!(
+            locations,
+            vec![
+                (
+                    "class".to_owned(),
+                    vec![location(&a, 1, 6), location(&a, 2, 9)]
+                ),
+                (
+                    "flex".to_owned(),
+                    vec![location(&a, 1, 13), location(&a, 2, 26), location(&b, 1, 3),]
+                ),
+                ("underline".to_owned(), vec![location(&a, 2, 16)]),
+            ]
+        );
+
+        // Columns in UTF-16 code units, e.g. for JavaScript
+        let mut scanner = Scanner::new(Some(vec![GlobEntry {
+            base: dir.to_string_lossy().to_string(),
+            pattern: "**/b.html".to_owned(),
+        }]))
+        .with_position_encoding(PositionEncoding::Utf16);
+
+        assert_eq!(
+            scanner.get_candidate_locations(),
+            vec![("flex".to_owned(), vec![location(&b, 1, 4)])]
+        );
+    }
+
+    #[test]
+    fn it_should_extract_candidates_spanning_multiple_lines() {
+        let (dir, sources) = create_project(&[(
+            "index.html",
+            "<div class=\"flex grid-cols-[repeat(auto-fill,\n    minmax(200px,1fr))] [mask-image:linear-gradient(\n      to_bottom,\n      black\n    )]\">\n",
+        )]);
+
+        let mut scanner = Scanner::new(Some(sources));
+        let candidates = scanner.scan();
+
+        assert!(candidates.contains(&"flex".to_owned()));
+        assert!(candidates.contains(&"grid-cols-[repeat(auto-fill,minmax(200px,1fr))]".to_owned()));
+        assert!(candidates.contains(&"[mask-image:linear-gradient(to_bottom,black)]".to_owned()));
+
+        let file = format!(
+            "{}/index.html",
+            format!("{}", dunce::canonicalize(&dir).unwrap().display()).replace('\\', "/")
+        );
+
+        let locations = scanner.get_candidate_locations();
+        let location_of = |candidate: &str| {
+            locations
+                .iter()
+                .find(|(c, _)| c == candidate)
+                .map(|(_, locations)| locations.clone())
+                .unwrap()
+        };
+
+        assert_eq!(
+            location_of("grid-cols-[repeat(auto-fill,minmax(200px,1fr))]"),
+            vec![CandidateLocation {
+                file: file.clone(),
+                line: 1,
+                column: 18,
+            }]
+        );
+        assert_eq!(
+            location_of("[mask-image:linear-gradient(to_bottom,black)]"),
+            vec![CandidateLocation {
+                file,
+                line: 2,
+                column: 25,
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_report_problems_with_sources() {
+        // Create a temporary working directory
+        let dir = tempdir().unwrap().into_path();
+
+        // Initialize this directory as a git repository
+        let _ = Command::new("git").arg("init").current_dir(&dir).output();
+
+        // Create files
+        create_files_in(&dir, &[("index.html", "flex"), ("src/index.html", "flex")]);
+
+        let base = dir.to_string_lossy().to_string();
+        let source = |pattern: &str| GlobEntry {
+            base: base.clone(),
+            pattern: pattern.to_owned(),
+        };
+
+        let mut scanner = Scanner::new(Some(vec![
+            // Valid sources
+            source("*.html"),
+            source("src"),
+            // Base doesn't exist
+            source("missing/**/*.html"),
+            source("missing"),
+            // Base exists, but nothing matches
+            source("src/**/*.vue"),
+            // Invalid brace expansion
+            source("src/**/*.{html,vue"),
+        ]));
+
+        assert_eq!(
+            scanner.get_diagnostics(),
+            vec![
+                Diagnostic::InvalidBraceExpansion {
+                    base: base.clone(),
+                    pattern: "src/**/*.{html,vue".to_owned(),
+                },
+                Diagnostic::MissingSourceBase {
+                    base: base.clone(),
+                    pattern: "missing/**/*.html".to_owned(),
+                },
+                Diagnostic::MissingSourceBase {
+                    base: base.clone(),
+                    pattern: "missing".to_owned(),
+                },
+                Diagnostic::EmptySource {
+                    base: base.clone(),
+                    pattern: "src/**/*.vue".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_report_unreadable_files() {
+        // Create a temporary working directory
+        let dir = tempdir().unwrap().into_path();
+
+        // A directory can't be read as a file
+        fs::create_dir_all(dir.join("folder.html")).unwrap();
+
+        let mut scanner = Scanner::new(None);
+        scanner.scan_content(vec![ChangedContent::File(
+            dir.join("folder.html"),
+            "html".into(),
+        )]);
+
+        let diagnostics = scanner.get_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(
+            &diagnostics[0],
+            Diagnostic::UnreadableFile { path, .. } if path == &dir.join("folder.html")
+        ));
+
+        // Once the file can be read, the diagnostic is gone
+        fs::remove_dir_all(dir.join("folder.html")).unwrap();
+        create_files_in(&dir, &[("folder.html", "flex")]);
+
+        scanner.scan_content(vec![ChangedContent::File(
+            dir.join("folder.html"),
+            "html".into(),
+        )]);
+
+        assert!(scanner.get_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn it_should_scan_once_relative_to_the_base() {
+        // Create a temporary working directory
+        let dir = tempdir().unwrap().into_path();
+
+        // Initialize this directory as a git repository
+        let _ = Command::new("git").arg("init").current_dir(&dir).output();
+
+        // Create files
+        create_files_in(
+            &dir,
+            &[
+                ("index.html", "content-['index.html']"),
+                ("src/index.html", "content-['src/index.html']"),
+                ("foo.styl", "content-['foo.styl']"),
+            ],
+        );
+
+        let base = dunce::canonicalize(&dir).unwrap();
+        let base_str = base.to_string_lossy().to_string();
+
+        // Relative sources are resolved against the base
+        let result = tailwindcss_oxide::scan(ScanOptions {
+            base: Some(base_str.clone()),
+            sources: vec![
+                GlobEntry {
+                    base: "src".into(),
+                    pattern: "**/*.html".into(),
+                },
+                GlobEntry {
+                    base: ".".into(),
+                    pattern: "*.styl".into(),
+                },
+            ],
+        });
+
+        assert_eq!(
+            result.candidates,
+            vec!["content-['foo.styl']", "content-['src/index.html']"]
+        );
+
+        let mut files = result.files;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                format!("{}/foo.styl", base_str.replace('\\', "/")),
+                format!("{}/src/index.html", base_str.replace('\\', "/")),
+            ]
+        );
+        assert!(result.diagnostics.is_empty());
+
+        // Without sources, the base is scanned using automatic source detection
+        let result = tailwindcss_oxide::scan(ScanOptions {
+            base: Some(base_str.clone()),
+            sources: vec![],
+        });
+
+        assert_eq!(
+            result.candidates,
+            vec!["content-['index.html']", "content-['src/index.html']"]
+        );
+        assert_eq!(result.globs[0].base, base_str);
+    }
+
+    #[test]
+    fn it_should_report_changes_since_the_previous_scan() {
+        let (dir, sources) = create_project(&[
+            ("a.html", "content-['a.html'] shared"),
+            ("b.html", "content-['b.html'] shared"),
+        ]);
+        let base = format!("{}", dunce::canonicalize(&dir).unwrap().display()).replace('\\', "/");
+
+        let mut scanner = Scanner::new(Some(sources));
+
+        // Everything is new on the first scan
+        assert_eq!(
+            scanner.scan_changes(),
+            ScanChanges {
+                added: vec![
+                    "content-['a.html']".to_owned(),
+                    "content-['b.html']".to_owned(),
+                    "shared".to_owned(),
+                ],
+                removed: vec![],
+                changed_files: vec![format!("{}/a.html", base), format!("{}/b.html", base)],
+            }
+        );
+
+        // Nothing changed
+        assert_eq!(scanner.scan_changes(), ScanChanges::default());
+
+        // We have to sleep because it might run too fast (seriously) and the
+        // mtimes of the files end up being the same as the last time we
+        // checked them
+        sleep(Duration::from_millis(100));
+
+        create_files_in(&dir, &[("a.html", "content-['a.html/changed'] shared")]);
+        fs::remove_file(dir.join("b.html")).unwrap();
+
+        assert_eq!(
+            scanner.scan_changes(),
+            ScanChanges {
+                added: vec!["content-['a.html/changed']".to_owned()],
+                removed: vec![
+                    "content-['a.html']".to_owned(),
+                    "content-['b.html']".to_owned(),
+                ],
+                changed_files: vec![format!("{}/a.html", base), format!("{}/b.html", base)],
+            }
+        );
+
+        // Candidates that are removed and added again in between are not reported
+        sleep(Duration::from_millis(100));
+        create_files_in(&dir, &[("a.html", "shared")]);
+        scanner.scan();
+        sleep(Duration::from_millis(100));
+        create_files_in(&dir, &[("a.html", "content-['a.html/changed'] shared")]);
+
+        assert_eq!(
+            scanner.scan_changes(),
+            ScanChanges {
+                added: vec![],
+                removed: vec![],
+                changed_files: vec![format!("{}/a.html", base)],
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_restore_the_scanner_from_a_cache() {
+        let (dir, sources) = create_project(&[
+            ("a.html", "content-['a.html']"),
+            ("b.html", "content-['b.html']"),
+            ("src/c.html", "content-['src/c.html']"),
+        ]);
+
+        let cache_dir = tempdir().unwrap().into_path();
+        let cache = cache_dir.join("scanner.cache");
+
+        let mut scanner = Scanner::new(Some(sources.clone()));
+        let candidates = scanner.scan();
+        scanner.save_cache(&cache).unwrap();
+
+        // Change the contents of `a.html` without changing its size or modification time. The
+        // cached candidates are used, which proves that the file is not extracted again.
+        let mtime = fs::metadata(dir.join("a.html"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        create_files_in(&dir, &[("a.html", "content-['A.HTML']")]);
+        fs::File::options()
+            .write(true)
+            .open(dir.join("a.html"))
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        // We have to sleep because it might run too fast (seriously) and the
+        // mtimes of the files end up being the same as the last time we
+        // checked them
+        sleep(Duration::from_millis(100));
+
+        // Files that changed are extracted again
+        create_files_in(&dir, &[("b.html", "content-['b.html/changed']")]);
+
+        // New files are picked up
+        create_files_in(&dir, &[("src/d.html", "content-['src/d.html']")]);
+
+        let mut restored = Scanner::new(Some(sources.clone()));
+        assert!(restored.load_cache(&cache).unwrap());
+
+        assert_eq!(
+            restored.scan(),
+            vec![
+                "content-['a.html']".to_owned(),
+                "content-['b.html/changed']".to_owned(),
+                "content-['src/c.html']".to_owned(),
+                "content-['src/d.html']".to_owned(),
+            ]
+        );
+        assert_ne!(restored.scan(), candidates);
+
+        // A cache for different sources is not used
+        let mut other = Scanner::new(Some(vec![GlobEntry {
+            base: dir.join("src").to_string_lossy().to_string(),
+            pattern: "**/*".to_owned(),
+        }]));
+        assert!(!other.load_cache(&cache).unwrap());
+
+        // The same sources written differently use the cache
+        let mut same = Scanner::new(Some(vec![GlobEntry {
+            base: dir.join("src").join("..").to_string_lossy().to_string(),
+            pattern: "**/*".to_owned(),
+        }]));
+        assert!(same.load_cache(&cache).unwrap());
+
+        // A cache for a different change detection mode is not used
+        let mut other =
+            Scanner::new(Some(sources.clone())).with_change_detection(ChangeDetection::ContentHash);
+        assert!(!other.load_cache(&cache).unwrap());
+
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+        // A corrupt cache is rejected
+        fs::write(&cache, b"garbage").unwrap();
+        assert!(Scanner::new(Some(sources)).load_cache(&cache).is_err());
+    }
+
+    #[test]
+    fn it_should_detect_changes_by_content_hash() {
+        let (dir, sources) = create_project(&[
+            ("a.html", "content-['a.html']"),
+            ("b.html", "content-['b.html']"),
+        ]);
+        let base = format!("{}", dunce::canonicalize(&dir).unwrap().display()).replace('\\', "/");
+
+        let mut scanner =
+            Scanner::new(Some(sources)).with_change_detection(ChangeDetection::ContentHash);
+        scanner.scan_changes();
+
+        // We have to sleep because it might run too fast (seriously) and the
+        // mtimes of the files end up being the same as the last time we
+        // checked them
+        sleep(Duration::from_millis(100));
+
+        // Rewriting a file with the same contents is not a change
+        create_files_in(&dir, &[("a.html", "content-['a.html']")]);
+
+        // Changing a file without changing its size or modification time is a change
+        let mtime = fs::metadata(dir.join("b.html"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        create_files_in(&dir, &[("b.html", "content-['B.HTML']")]);
+        fs::File::options()
+            .write(true)
+            .open(dir.join("b.html"))
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        assert_eq!(
+            scanner.scan_changes(),
+            ScanChanges {
+                added: vec!["content-['B.HTML']".to_owned()],
+                removed: vec!["content-['b.html']".to_owned()],
+                changed_files: vec![format!("{}/b.html", base)],
+            }
+        );
+
+        // New files are detected without relying on directory modification times
+        let mtime = fs::metadata(&dir).unwrap().modified().unwrap();
+        create_files_in(&dir, &[("c.html", "content-['c.html']")]);
+        fs::File::open(&dir).unwrap().set_modified(mtime).unwrap();
+
+        assert_eq!(
+            scanner.scan_changes(),
+            ScanChanges {
+                added: vec!["content-['c.html']".to_owned()],
+                removed: vec![],
+                changed_files: vec![format!("{}/c.html", base)],
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_extract_files_in_batches_below_the_memory_limit() {
+        let (_, sources) = create_project(&[
+            ("a.html", "content-['a.html']"),
+            ("b.html", "content-['b.html']"),
+            ("c.html", "content-['c.html'] content-['shared']"),
+            ("d.html", "content-['d.html'] content-['shared']"),
+        ]);
+
+        let mut unbounded = Scanner::new(Some(sources.clone()));
+
+        // Every file is bigger than the limit, so each file is extracted on its own
+        let mut bounded = Scanner::new(Some(sources)).with_memory_limit(8);
+
+        assert_eq!(bounded.scan(), unbounded.scan());
+        assert_eq!(
+            bounded.scan_content(vec![
+                ChangedContent::Content("content-['e.html']".into(), "html".into()),
+                ChangedContent::Content("content-['f.html']".into(), "html".into()),
+            ]),
+            vec!["content-['e.html']", "content-['f.html']"]
+        );
+    }
+
+    #[test]
+    fn it_should_scan_on_a_dedicated_thread_pool() {
+        let (_, sources) = create_project(&[
+            ("a.html", "content-['a.html']"),
+            ("nested/b.html", "content-['b.html']"),
+        ]);
+
+        let mut global = Scanner::new(Some(sources.clone()));
+        let mut single_threaded = Scanner::new(Some(sources.clone())).with_threads(1);
+        let mut multi_threaded = Scanner::new(Some(sources)).with_threads(2);
+
+        let expected = global.scan();
+        assert_eq!(single_threaded.scan(), expected);
+        assert_eq!(multi_threaded.scan(), expected);
+        assert_eq!(single_threaded.get_files(), global.get_files());
+        assert_eq!(multi_threaded.get_globs(), global.get_globs());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_should_watch_for_changes() {
+        let (dir, sources) = create_project(&[
+            ("a.html", "content-['a.html']"),
+            ("b.html", "content-['b.html']"),
+        ]);
+        let base = format!("{}", dunce::canonicalize(&dir).unwrap().display()).replace('\\', "/");
+
+        let mut scanner = Scanner::new(Some(sources));
+        scanner.scan_changes();
+
+        let batches = scanner.watch(Duration::from_millis(50)).unwrap();
+
+        create_files_in(
+            &dir,
+            &[
+                ("a.html", "content-['A.HTML']"),
+                ("sub/c.html", "content-['c.html']"),
+            ],
+        );
+        fs::remove_file(dir.join("b.html")).unwrap();
+
+        // Changes can be spread over multiple batches
+        let mut added = vec![];
+        let mut removed = vec![];
+        let mut changed_files = vec![];
+        while let Ok(batch) = batches.recv_timeout(Duration::from_millis(500)) {
+            let changes = scanner.scan_watch_batch(&batch);
+            added.extend(changes.added);
+            removed.extend(changes.removed);
+            changed_files.extend(changes.changed_files);
+        }
+
+        added.sort();
+        removed.sort();
+        changed_files.sort();
+        changed_files.dedup();
+
+        assert_eq!(
+            ScanChanges {
+                added,
+                removed,
+                changed_files,
+            },
+            ScanChanges {
+                added: vec![
+                    "content-['A.HTML']".to_owned(),
+                    "content-['c.html']".to_owned(),
+                ],
+                removed: vec![
+                    "content-['a.html']".to_owned(),
+                    "content-['b.html']".to_owned(),
+                ],
+                changed_files: vec![
+                    format!("{}/a.html", base),
+                    format!("{}/b.html", base),
+                    format!("{}/sub/c.html", base),
+                ],
+            }
+        );
+
+        // No more batches are delivered once we stop watching
+        scanner.unwatch();
+        create_files_in(&dir, &[("d.html", "content-['d.html']")]);
+        assert!(batches.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+
     #[test]
     fn skips_ignore_files_outside_of_a_repo() {
         // Create a temporary working directory